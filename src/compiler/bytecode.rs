@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use crate::{
+    lexer::tokens::{Comparison, Location, TokenType},
+    parser::nodes::{self, Node},
+};
+
+/// A single instruction of the VM, jumps are indexes into Chunk::ops
+#[derive(Debug, Clone)]
+pub enum Op {
+    // ----- values -----
+    Number(f64),
+    String(Rc<str>),
+    Boolean(bool),
+    Null,
+    /// Pops n values into an array
+    Array(usize),
+    MakeFunction(Rc<FunctionTemplate>),
+
+    // ----- stack -----
+    Pop,
+    /// Removes the value under the top of the stack
+    Nip,
+    Swap,
+
+    // ----- variables -----
+    /// Pushes a variable, using the resolver's address when there is one
+    Load(Rc<str>, Option<nodes::Address>),
+    /// Defines a variable with the top of the stack, leaving it there
+    Declare(Rc<str>, bool),
+    DeclarePattern(Rc<nodes::DeclareType>, bool),
+    /// Modifies a variable with the top of the stack, leaving it there
+    Store(Rc<str>, Option<nodes::Address>),
+
+    // ----- operators -----
+    Arithmetic(TokenType),
+    Compare(Comparison),
+    Not,
+    Negate,
+    LengthOf,
+    /// Converts the top of the stack into a boolean
+    Truthy,
+
+    // ----- members & calls -----
+    Member(Rc<str>),
+    Index,
+    /// Calls with the positional arguments, then the named arguments with the given names
+    Call(usize, Rc<[String]>),
+
+    // ----- control flow -----
+    Jump(usize),
+    /// Pops the top of the stack, jumping if it is not truthy
+    JumpIfFalse(usize),
+    /// Pops the top of the stack, jumping if it is truthy
+    JumpIfTrue(usize),
+    /// Jumps if the top of the stack is not null, otherwise pops it
+    JumpIfNotNull(usize),
+    PushScope,
+    PopScope,
+    Return,
+    /// Returns a call like Call, which is made by the function returning it
+    TailCall(usize, Rc<[String]>),
+
+    // ----- loops -----
+    /// Marks the start of the loop with the index into Chunk::loops, so break and continue can find it
+    LoopEnter(usize),
+    LoopExit,
+    /// Pops a value, starting to iterate it
+    IterStart,
+    /// Gets the next value of the current iterator, pushing a scope with the index and value,
+    /// or jumps if there are none left
+    ForNext(Rc<(nodes::Symbol, Option<nodes::Symbol>)>, usize),
+    IterEnd,
+
+    // ----- match -----
+    /// Checks the subject on the top of the stack against the pattern, pushing a scope with its
+    /// bindings, or jumps if it did not match
+    MatchPattern(Rc<nodes::MatchPattern>, usize),
+
+    // ----- other -----
+    Debug,
+    Await,
+    /// Runs the node with the tree-walking interpreter, for nodes which are not compiled yet
+    Eval(Rc<Node>),
+}
+
+/// What is needed to create a function value, the body is shared by every value made from it
+#[derive(Debug, Clone)]
+pub struct FunctionTemplate {
+    pub name: Option<String>,
+    pub args: Rc<nodes::Arguments>,
+    pub body: Rc<nodes::Block>,
+    pub is_generator: bool,
+    pub is_async: bool,
+    /// Generators are ran by the interpreter, as they stop between statements
+    pub chunk: Option<Rc<Chunk>>,
+}
+
+/// Where a loop's break and continue go
+#[derive(Debug, Clone)]
+pub struct LoopInfo {
+    pub label: Option<nodes::Symbol>,
+    pub continue_to: usize,
+    pub break_to: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+    pub ops: Vec<Op>,
+    /// The location of each op, used for errors
+    pub locations: Vec<Location>,
+    pub loops: Vec<LoopInfo>,
+    /// Function bodies can return, the top level cannot
+    pub is_function: bool,
+}
@@ -0,0 +1,495 @@
+use std::rc::Rc;
+
+use crate::{
+    lexer::tokens::{Location, Logical},
+    parser::nodes::{self, DeclareType, InterruptType, MatchCaseType, Node, UnaryType},
+};
+
+use bytecode::{Chunk, FunctionTemplate, LoopInfo, Op};
+
+pub mod bytecode;
+
+/// Compiles nodes into bytecode for the VM
+///
+/// Anything which is not compiled yet becomes an Op::Eval, so the tree-walking interpreter runs it
+/// with the VM's current scope, meaning the VM always has the same behaviour
+pub struct Compiler {
+    chunk: Chunk,
+}
+
+impl Compiler {
+    pub fn compile(node: &Node) -> Chunk {
+        let mut compiler = Compiler {
+            chunk: Chunk::default(),
+        };
+        compiler.node(node);
+        compiler.chunk
+    }
+
+    pub fn compile_function(body: &nodes::Block) -> Chunk {
+        let mut compiler = Compiler {
+            chunk: Chunk {
+                is_function: true,
+                ..Default::default()
+            },
+        };
+        compiler.block(body);
+        compiler.chunk
+    }
+
+    fn emit(&mut self, op: Op, location: &Location) -> usize {
+        self.chunk.ops.push(op);
+        self.chunk.locations.push(location.clone());
+        self.chunk.ops.len() - 1
+    }
+
+    fn here(&self) -> usize {
+        self.chunk.ops.len()
+    }
+
+    /// Points the jump at the given index to the next op
+    fn patch(&mut self, at: usize) {
+        let target = self.here();
+        match &mut self.chunk.ops[at] {
+            Op::Jump(to)
+            | Op::JumpIfFalse(to)
+            | Op::JumpIfTrue(to)
+            | Op::JumpIfNotNull(to)
+            | Op::ForNext(_, to)
+            | Op::MatchPattern(_, to) => *to = target,
+            op => unreachable!("Cannot patch {:?}", op),
+        }
+    }
+
+    /// Whether a member / call chain has a ?. anywhere, which can skip the rest of the chain
+    fn is_optional_chain(node: &Node) -> bool {
+        match node {
+            Node::Member(expr) => expr.optional || Compiler::is_optional_chain(&expr.left),
+            Node::Call(expr) => expr.optional || Compiler::is_optional_chain(&expr.left),
+            _ => false,
+        }
+    }
+
+    /// Whether a call can be compiled, rather than ran with Eval, as it has no ?. or spread
+    fn is_simple_call(node: &Node) -> bool {
+        match node {
+            Node::Call(expr) => {
+                !Compiler::is_optional_chain(node)
+                    && !expr.args.iter().any(|x| matches!(x, Node::Spread(_)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Pushes what is being called and its arguments, giving the names of the named arguments
+    fn call_arguments(&mut self, expr: &nodes::Call) -> Rc<[String]> {
+        self.node(&expr.left);
+        for arg in &expr.args {
+            self.node(arg);
+        }
+        for (_, arg) in &expr.named_args {
+            self.node(arg);
+        }
+
+        expr.named_args
+            .iter()
+            .map(|(name, _)| name.value.clone())
+            .collect::<Vec<String>>()
+            .into()
+    }
+
+    fn node(&mut self, node: &Node) {
+        let location = node.location();
+
+        match node {
+            Node::Number(expr) => {
+                self.emit(Op::Number(expr.value), location);
+            }
+            Node::ZString(expr) => {
+                self.emit(Op::String(Rc::from(expr.value.as_str())), location);
+            }
+            Node::Symbol(expr) => {
+                self.emit(
+                    Op::Load(Rc::from(expr.value.as_str()), expr.address),
+                    location,
+                );
+            }
+            Node::Array(expr) if !expr.items.iter().any(|x| matches!(x, Node::Spread(_))) => {
+                for item in &expr.items {
+                    self.node(item);
+                }
+                self.emit(Op::Array(expr.items.len()), location);
+            }
+            Node::Function(expr) => {
+                let chunk = match expr.is_generator {
+                    true => None,
+                    false => Some(Rc::from(Compiler::compile_function(&expr.body))),
+                };
+                let template = FunctionTemplate {
+                    name: expr.name.as_ref().map(|x| x.value.clone()),
+                    args: Rc::from(expr.args.clone()),
+                    body: Rc::from(expr.body.clone()),
+                    is_generator: expr.is_generator,
+                    is_async: expr.is_async,
+                    chunk,
+                };
+                self.emit(Op::MakeFunction(Rc::from(template)), location);
+            }
+
+            Node::Block(expr) => self.block(expr),
+            Node::Debug(expr) => {
+                self.node(&expr.node);
+                self.emit(Op::Debug, location);
+            }
+            Node::Await(expr) => {
+                self.node(&expr.value);
+                self.emit(Op::Await, location);
+            }
+
+            Node::Declare(expr) => {
+                match &expr.value {
+                    Some(value) => self.node(value),
+                    None => {
+                        self.emit(Op::Null, location);
+                    }
+                };
+
+                match &expr.assignee {
+                    DeclareType::Symbol(symbol) => self.emit(
+                        Op::Declare(Rc::from(symbol.value.as_str()), expr.is_const),
+                        &symbol.location,
+                    ),
+                    pattern => self.emit(
+                        Op::DeclarePattern(Rc::from(pattern.clone()), expr.is_const),
+                        location,
+                    ),
+                };
+            }
+            Node::Assign(expr) if matches!(*expr.assignee, Node::Symbol(_)) => {
+                let Node::Symbol(ref symbol) = *expr.assignee else {
+                    unreachable!()
+                };
+                let name: Rc<str> = Rc::from(symbol.value.as_str());
+
+                self.node(&expr.value);
+                if let Some(operator) = &expr.operator {
+                    self.emit(Op::Load(name.clone(), symbol.address), &symbol.location);
+                    self.emit(Op::Swap, location);
+                    self.emit(Op::Arithmetic(operator.clone()), location);
+                }
+                self.emit(Op::Store(name, symbol.address), &symbol.location);
+            }
+
+            Node::Arithmetic(expr) => {
+                self.node(&expr.left);
+                self.node(&expr.right);
+                self.emit(Op::Arithmetic(expr.t.clone()), location);
+            }
+            Node::Comp(expr) => {
+                self.node(&expr.left);
+                self.node(&expr.right);
+                self.emit(Op::Compare(expr.t.clone()), location);
+            }
+            Node::Logical(expr) => {
+                // The right is only ran if it decides the result
+                self.node(&expr.left);
+                let (short, value) = match expr.t {
+                    Logical::And => (self.emit(Op::JumpIfFalse(0), location), false),
+                    Logical::Or => (self.emit(Op::JumpIfTrue(0), location), true),
+                };
+                self.node(&expr.right);
+                self.emit(Op::Truthy, location);
+                let end = self.emit(Op::Jump(0), location);
+                self.patch(short);
+                self.emit(Op::Boolean(value), location);
+                self.patch(end);
+            }
+            Node::Coalesce(expr) => {
+                self.node(&expr.left);
+                let end = self.emit(Op::JumpIfNotNull(0), location);
+                self.node(&expr.right);
+                self.patch(end);
+            }
+            Node::Unary(expr)
+                if !expr.is_right
+                    && matches!(
+                        expr.t,
+                        UnaryType::Not | UnaryType::Minus | UnaryType::LengthOf
+                    ) =>
+            {
+                self.node(&expr.value);
+                self.emit(
+                    match expr.t {
+                        UnaryType::Not => Op::Not,
+                        UnaryType::Minus => Op::Negate,
+                        _ => Op::LengthOf,
+                    },
+                    location,
+                );
+            }
+
+            Node::Member(expr)
+                if !Compiler::is_optional_chain(node)
+                    && (expr.computed || matches!(*expr.right, Node::Symbol(_))) =>
+            {
+                self.node(&expr.left);
+                match *expr.right {
+                    Node::Symbol(ref symbol) if !expr.computed => {
+                        self.emit(Op::Member(Rc::from(symbol.value.as_str())), location);
+                    }
+                    ref right => {
+                        self.node(right);
+                        self.emit(Op::Index, location);
+                    }
+                }
+            }
+            Node::Call(expr) if Compiler::is_simple_call(node) => {
+                let names = self.call_arguments(expr);
+                self.emit(Op::Call(expr.args.len(), names), location);
+            }
+
+            Node::If(expr) => {
+                self.node(&expr.test);
+                let alternate = self.emit(Op::JumpIfFalse(0), location);
+                self.node(&expr.succss);
+                let end = self.emit(Op::Jump(0), location);
+                self.patch(alternate);
+                match &expr.alternate {
+                    Some(alternate) => self.node(alternate),
+                    None => {
+                        self.emit(Op::Null, location);
+                    }
+                }
+                self.patch(end);
+            }
+            Node::Match(expr) => self.match_expr(expr),
+            Node::WhileLoop(expr) => {
+                let info = self.start_loop(&expr.label, location);
+                let start = self.here();
+                self.node(&expr.test);
+                let done = self.emit(Op::JumpIfFalse(0), location);
+                self.node(&expr.body);
+                self.emit(Op::Pop, location);
+                self.emit(Op::Jump(start), location);
+
+                self.patch(done);
+                self.end_loop(info, start, &expr.on_break, false, location);
+            }
+            Node::For(expr) => {
+                let iterator_location = expr.iterator.location();
+                self.node(&expr.iterator);
+                self.emit(Op::IterStart, iterator_location);
+
+                let info = self.start_loop(&expr.label, location);
+                let start = self.emit(
+                    Op::ForNext(
+                        Rc::from((expr.index_symbol.clone(), expr.value_symbol.clone())),
+                        0,
+                    ),
+                    iterator_location,
+                );
+                self.node(&expr.block);
+                self.emit(Op::Pop, location);
+                self.emit(Op::PopScope, location);
+                self.emit(Op::Jump(start), location);
+
+                self.patch(start);
+                self.end_loop(info, start, &expr.on_break, true, location);
+            }
+
+            Node::Interrupt(nodes::Interrupt {
+                t: InterruptType::Return(Some(value)),
+                tail_call: true,
+                ..
+            }) if Compiler::is_simple_call(value) => {
+                let Node::Call(expr) = &**value else {
+                    unreachable!()
+                };
+                let names = self.call_arguments(expr);
+                self.emit(Op::TailCall(expr.args.len(), names), location);
+            }
+            Node::Interrupt(nodes::Interrupt {
+                t: InterruptType::Return(value),
+                ..
+            }) => {
+                match value {
+                    Some(value) => self.node(value),
+                    None => {
+                        self.emit(Op::Null, location);
+                    }
+                }
+                self.emit(Op::Return, location);
+            }
+
+            _ => {
+                self.emit(Op::Eval(Rc::from(node.clone())), location);
+            }
+        }
+    }
+
+    /// Compiles the statements of a block in a new scope, leaving the last value
+    fn block(&mut self, expr: &nodes::Block) {
+        self.emit(Op::PushScope, &expr.location);
+
+        for (i, node) in expr.nodes.iter().enumerate() {
+            if i != 0 {
+                self.emit(Op::Pop, &expr.location);
+            }
+            self.node(node);
+        }
+
+        if expr.nodes.is_empty() {
+            self.emit(Op::Null, &expr.location);
+        }
+
+        self.emit(Op::PopScope, &expr.location);
+    }
+
+    fn start_loop(&mut self, label: &Option<nodes::Symbol>, location: &Location) -> usize {
+        self.chunk.loops.push(LoopInfo {
+            label: label.clone(),
+            continue_to: 0,
+            break_to: 0,
+        });
+        let info = self.chunk.loops.len() - 1;
+        self.emit(Op::LoopEnter(info), location);
+        info
+    }
+
+    /// Compiles the end of a loop, where a finished loop gives null and a broken one runs its else
+    fn end_loop(
+        &mut self,
+        info: usize,
+        continue_to: usize,
+        on_break: &Option<Box<Node>>,
+        has_iter: bool,
+        location: &Location,
+    ) {
+        self.emit(Op::LoopExit, location);
+        if has_iter {
+            self.emit(Op::IterEnd, location);
+        }
+        self.emit(Op::Null, location);
+        let end = self.emit(Op::Jump(0), location);
+
+        let break_to = self.emit(Op::LoopExit, location);
+        if has_iter {
+            self.emit(Op::IterEnd, location);
+        }
+        match on_break {
+            Some(on_break) => self.node(on_break),
+            None => {
+                self.emit(Op::Null, location);
+            }
+        }
+        self.patch(end);
+
+        self.chunk.loops[info].continue_to = continue_to;
+        self.chunk.loops[info].break_to = break_to;
+    }
+
+    fn match_expr(&mut self, expr: &nodes::Match) {
+        let location = &expr.location;
+        self.node(&expr.test);
+
+        let mut ends: Vec<usize> = vec![];
+        let mut has_else = false;
+
+        for case in &expr.cases {
+            match case {
+                MatchCaseType::MatchCase(case) => {
+                    // The guard and the case share the scope with the bindings
+                    let next = self.emit(
+                        Op::MatchPattern(Rc::from(case.pattern.clone()), 0),
+                        location,
+                    );
+
+                    let failed_guard = case.guard.as_ref().map(|guard| {
+                        self.node(guard);
+                        self.emit(Op::JumpIfFalse(0), location)
+                    });
+
+                    self.node(&case.success);
+                    self.emit(Op::PopScope, location);
+                    ends.push(self.emit(Op::Jump(0), location));
+
+                    if let Some(failed_guard) = failed_guard {
+                        self.patch(failed_guard);
+                        self.emit(Op::PopScope, location);
+                    }
+                    self.patch(next);
+                }
+                MatchCaseType::Else(value) => {
+                    self.node(value);
+                    has_else = true;
+                    break;
+                }
+            }
+        }
+
+        if !has_else {
+            self.emit(Op::Null, location);
+        }
+
+        for end in ends {
+            self.patch(end);
+        }
+
+        // Removes the subject from under the result
+        self.emit(Op::Nip, location);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::test::run;
+
+    #[test]
+    fn vm_matches_tree_walker() {
+        let cases = [
+            ("let a = 0; for i in 0..10 { if i == 3 { continue } a += i } a", "42"),
+            (
+                "let r = []; outer: for i in 0..3 { for j in 0..3 { if j == 1 { continue outer } r = [...r, [i, j]] } } r",
+                "[[0, 0], [1, 0], [2, 0]]",
+            ),
+            (
+                "func counter() { let n = 0; return func { n += 1; n } } let c = counter(); c(); c(); c()",
+                "3",
+            ),
+            (
+                "let [a, b, c] = [1, 2, 3]; let .{ x } = .{ x: 4 }; match [b, c] { [2, y] -> a + y + x, else -> 0 }",
+                "8",
+            ),
+            ("let r = try { throw 5 } catch e { e * 2 } finally { 1 }; r", "10"),
+            (
+                "let i = 0; while i < 5 { i += 1; if i == 3 { break } } else { i += 100 }; i",
+                "103",
+            ),
+            (
+                "func sum(n, t) { if n == 0 { return t } return sum(n - 1, t + n) } sum(2000, 0)",
+                "2001000",
+            ),
+            ("let name = \"vm\"; let n = 2; `{name} {n * 3}`", "vm 6"),
+            (
+                "struct P(x, y) { func len(self) { self.x + self.y } } enum E { A(v), B } [P(1, 2).len(), E.A(3).v, E.B.name(), P(1, 2) is P]",
+                "[3, 3, \"B\", true]",
+            ),
+            (
+                "func* g() { for i in 0..3 { yield i * 2 } } let o = .{ a: 1, b: .{ c: null } }; [[...g()], o.b?.c?.d ?? 7]",
+                "[[0, 2, 4], 7]",
+            ),
+            ("let a = 1; a()", "error: Cannot call a number"),
+        ];
+
+        for (code, expected) in cases {
+            let tree_walker = run(code, false);
+            let vm = run(code, true);
+            assert_eq!(
+                tree_walker, vm,
+                "Expected the VM to match the tree-walker for {}",
+                code
+            );
+            assert_eq!(tree_walker, expected, "Unexpected result for {}", code);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::fs;
+use std::{collections::HashMap, fs};
 
 use crate::{lexer::tokens::Location, runtime::values::RuntimeValue, util};
 
@@ -18,6 +18,7 @@ pub enum ErrorCode {
     CannotCoerce,
     AlreadyDefined,
     ConstantAssignment,
+    Frozen,
     InternalLockError,
     ScopeError,
     OutOfBounds,
@@ -33,11 +34,26 @@ pub enum ErrorCode {
     ChannelError,
     StructMappingError,
     InvalidArgumentsError,
+    StackOverflow,
+    NonExhaustiveMatch,
 
-    Break,
-    Continue,
+    Break(Option<String>),
+    Continue(Option<String>),
     Return(Option<RuntimeValue>),
+    /// A return of a call, which the function makes itself so its stack is reused
+    TailCall(Box<TailCall>),
+    BreakError,
+    ContinueError,
     ReturnError,
+    Thrown(RuntimeValue),
+    Uncaught,
+}
+
+#[derive(Debug, Clone)]
+pub struct TailCall {
+    pub function: RuntimeValue,
+    pub args: Vec<RuntimeValue>,
+    pub named: HashMap<String, RuntimeValue>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,19 +64,37 @@ pub struct ZephyrError {
 }
 
 impl ZephyrError {
+    /// Whether the error is actually control flow (break, continue, return)
+    pub fn is_interrupt(&self) -> bool {
+        matches!(
+            self.code,
+            ErrorCode::Break(_)
+                | ErrorCode::Continue(_)
+                | ErrorCode::Return(_)
+                | ErrorCode::TailCall(_)
+        )
+    }
+
     pub fn _visualise(&self, file_contents: String) -> String {
-        let mut string = format!(
+        let string = format!(
             "{}{:?} error: {}{}",
             util::colors::FG_RED,
-            if matches!(self.code, ErrorCode::Return(_)) {
-                ErrorCode::ReturnError
-            } else {
-                self.code.clone()
+            match self.code {
+                ErrorCode::Break(_) => ErrorCode::BreakError,
+                ErrorCode::Continue(_) => ErrorCode::ContinueError,
+                ErrorCode::Return(_) | ErrorCode::TailCall(_) => ErrorCode::ReturnError,
+                ErrorCode::Thrown(_) => ErrorCode::Uncaught,
+                _ => self.code.clone(),
             },
             self.message,
             util::colors::COLOR_RESET
         );
 
+        self.show_location(string, file_contents)
+    }
+
+    /// Adds the line the error is on below the message, pointing to where it is
+    fn show_location(&self, mut string: String, file_contents: String) -> String {
         if let Some(ref location) = self.location {
             let lines = file_contents.split('\n').collect::<Vec<&str>>();
             let result = if location.line >= lines.len() {
@@ -89,6 +123,23 @@ impl ZephyrError {
     }
 
     pub fn visualise(&self) -> String {
+        self._visualise(self.file_contents())
+    }
+
+    /// Shows the error as a warning, for problems which do not stop the code from running
+    pub fn visualise_warning(&self) -> String {
+        self.show_location(
+            format!(
+                "{}Warning: {}{}",
+                util::colors::FG_YELLOW,
+                self.message,
+                util::colors::COLOR_RESET
+            ),
+            self.file_contents(),
+        )
+    }
+
+    fn file_contents(&self) -> String {
         let mut file_contents: Result<String, String> = Err("<no file provided>".to_string());
 
         if let Some(ref location) = self.location {
@@ -99,9 +150,9 @@ impl ZephyrError {
                 }
             }
 
-            return self._visualise(file_contents.unwrap_or("<no file provided>".to_string()));
+            return file_contents.unwrap_or("<no file provided>".to_string());
         }
 
-        self._visualise("<no location>".to_string())
+        "<no location>".to_string()
     }
 }
@@ -1,3 +1,5 @@
+use std::{iter::Peekable, str::Chars};
+
 use crate::errors::{ErrorCode, ZephyrError};
 
 use super::tokens::{
@@ -20,7 +22,7 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
         };
         let current_value: String;
         let current_token: Option<TokenType>;
-        let mut current_length: usize = 0;
+        let current_length: usize;
 
         match char {
             '\n' => {
@@ -67,15 +69,56 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                         _ => {}
                     }
                 }
-                current_token = Some(TokenType::Multiplicative(Multiplicative::Divide));
-                current_length = 1;
-                current_value = char.to_string();
+                if chars.next_if_eq(&'=').is_some() {
+                    current_token = Some(TokenType::AssignOperator(Box::from(
+                        TokenType::Multiplicative(Multiplicative::Divide),
+                    )));
+                    current_length = 2;
+                    current_value = String::from("/=");
+                } else {
+                    current_token = Some(TokenType::Multiplicative(Multiplicative::Divide));
+                    current_length = 1;
+                    current_value = char.to_string();
+                }
             }
 
-            _ if char.is_numeric() => {
+            _ if char.is_ascii_digit() => {
                 let mut value = char.to_string();
-                while chars.peek().unwrap_or(&'n').is_numeric() {
-                    value.push(chars.next().unwrap())
+                while let Some(&next) = chars.peek() {
+                    let is_decimal = !matches!(
+                        value.get(..2),
+                        Some("0x" | "0X" | "0b" | "0B" | "0o" | "0O")
+                    );
+
+                    let continues = match next {
+                        _ if next.is_alphanumeric() || next == '_' => true,
+                        // Only a decimal point when followed by a digit, so 1..2 is still a range
+                        '.' => {
+                            let mut ahead = chars.clone();
+                            ahead.next();
+                            is_decimal
+                                && !value.contains(['.', 'e', 'E'])
+                                && ahead.peek().is_some_and(|x| x.is_ascii_digit())
+                        }
+                        '+' | '-' => is_decimal && value.ends_with(['e', 'E']),
+                        _ => false,
+                    };
+
+                    if !continues {
+                        break;
+                    }
+                    value.push(chars.next().unwrap());
+                }
+
+                if let Err(message) = parse_number(&value) {
+                    return Err(ZephyrError {
+                        code: ErrorCode::InvalidNumber,
+                        message,
+                        location: Some(Location {
+                            end: current_char + value.len(),
+                            ..current_location
+                        }),
+                    });
                 }
 
                 current_token = Some(TokenType::Number);
@@ -84,57 +127,375 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
             }
 
             '"' => {
+                // """ starts a multi-line string
+                let mut ahead = chars.clone();
+                let multi_line = ahead.next() == Some('"') && ahead.next() == Some('"');
+                if multi_line {
+                    chars.next();
+                    chars.next();
+                }
+
                 let mut value = String::new();
-                while let Some(char) = chars.peek() {
-                    if *char == '"' || *char == '\n' {
-                        break;
-                    }
+                let mut line = current_line;
+                let mut column = current_char + if multi_line { 3 } else { 1 };
 
-                    if *char == '\\' {
-                        chars.next();
-                        if let Some(to_escape) = chars.next() {
-                            let result = match to_escape {
-                                '"' => '"',
-                                '\\' => '\\',
-                                'n' => '\n',
-                                't' => '\t',
-                                'r' => '\r',
-                                _ => {
-                                    return Err(ZephyrError {
-                                        code: ErrorCode::CannotEscape,
-                                        message: format!("Cannot escape {}", to_escape),
-                                        location: Some(current_location),
-                                    })
-                                }
-                            };
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Err(ZephyrError {
+                            code: ErrorCode::UnterminatedString,
+                            message: String::from("String not closed"),
+                            location: Some(current_location),
+                        });
+                    };
 
-                            current_length += 2;
-                            value.push(result);
-                            continue;
-                        } else {
+                    match c {
+                        '"' if !multi_line => {
+                            column += 1;
+                            break;
+                        }
+                        '"' if chars.peek() == Some(&'"') && {
+                            let mut ahead = chars.clone();
+                            ahead.next();
+                            ahead.next() == Some('"')
+                        } =>
+                        {
+                            chars.next();
+                            chars.next();
+                            column += 3;
+                            break;
+                        }
+                        '\n' if !multi_line => {
                             return Err(ZephyrError {
-                                code: ErrorCode::CannotEscape,
-                                message: String::from("Nothing to escape"),
+                                code: ErrorCode::UnterminatedString,
+                                message: String::from(
+                                    "String not closed, use \"\"\" for multi-line strings",
+                                ),
                                 location: Some(current_location),
                             });
                         }
+                        '\n' => {
+                            value.push(c);
+                            line += 1;
+                            column = 0;
+                        }
+                        '\\' => {
+                            let location = Location {
+                                start: column,
+                                end: column + 2,
+                                line,
+                                file_name: Some(file_name.clone()),
+                            };
+                            let Some(to_escape) = chars.next() else {
+                                return Err(ZephyrError {
+                                    code: ErrorCode::CannotEscape,
+                                    message: String::from("Nothing to escape"),
+                                    location: Some(location),
+                                });
+                            };
+
+                            let (result, length) = escape(to_escape, &mut chars, &location)?;
+                            value.push(result);
+                            column += 2 + length;
+                        }
+                        _ => {
+                            value.push(c);
+                            column += 1;
+                        }
+                    }
+                }
+
+                // The newline straight after """ is not part of the string
+                if multi_line {
+                    if let Some(stripped) = value
+                        .strip_prefix('\n')
+                        .or_else(|| value.strip_prefix("\r\n"))
+                    {
+                        value = stripped.to_string();
                     }
+                }
 
-                    current_length += 1;
-                    value.push(chars.next().unwrap());
+                tokens.push(Token {
+                    t: TokenType::String,
+                    value,
+                    location: Location {
+                        end: if line == current_line {
+                            column
+                        } else {
+                            current_char + 3
+                        },
+                        ..current_location
+                    },
+                });
+                current_line = line;
+                current_char = column;
+                continue;
+            }
+
+            // Raw strings, r"..." or r#"..."# where the amount of #s must match
+            'r' if matches!(chars.peek(), Some('"' | '#')) => {
+                let mut hashes = 0;
+                while chars.peek() == Some(&'#') {
+                    chars.next();
+                    hashes += 1;
                 }
 
-                if chars.next().is_none() {
+                if chars.next() != Some('"') {
                     return Err(ZephyrError {
-                        code: ErrorCode::UnterminatedString,
-                        message: String::from("String not closed"),
+                        code: ErrorCode::UnexpectedToken,
+                        message: String::from("Expected \" to start raw string"),
                         location: Some(current_location),
                     });
                 }
 
-                current_token = Some(TokenType::String);
-                current_length += 2;
-                current_value = value;
+                let mut value = String::new();
+                let mut line = current_line;
+                let mut column = current_char + hashes + 2;
+
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Err(ZephyrError {
+                            code: ErrorCode::UnterminatedString,
+                            message: format!(
+                                "Raw string not closed, expected \"{}",
+                                "#".repeat(hashes)
+                            ),
+                            location: Some(current_location),
+                        });
+                    };
+
+                    if c == '"' {
+                        let mut ahead = chars.clone();
+                        if (0..hashes).all(|_| ahead.next() == Some('#')) {
+                            for _ in 0..hashes {
+                                chars.next();
+                            }
+                            column += hashes + 1;
+                            break;
+                        }
+                    }
+
+                    if c == '\n' {
+                        line += 1;
+                        column = 0;
+                    } else {
+                        column += 1;
+                    }
+                    value.push(c);
+                }
+
+                tokens.push(Token {
+                    t: TokenType::String,
+                    value,
+                    location: Location {
+                        end: if line == current_line {
+                            column
+                        } else {
+                            current_char + hashes + 2
+                        },
+                        ..current_location
+                    },
+                });
+                current_line = line;
+                current_char = column;
+                continue;
+            }
+
+            '`' => {
+                let start_location = current_location.clone();
+                let mut template_tokens: Vec<Token> = vec![Token {
+                    t: TokenType::TemplateStart,
+                    value: String::from("`"),
+                    location: Location {
+                        end: current_char + 1,
+                        ..start_location.clone()
+                    },
+                }];
+                current_char += 1;
+
+                let mut value = String::new();
+                let mut value_location = Location {
+                    start: current_char,
+                    end: current_char,
+                    line: current_line,
+                    file_name: Some(file_name.clone()),
+                };
+
+                // Pushes the currently collected text as a string part
+                let flush = |value: &mut String, location: &Location, tokens: &mut Vec<Token>| {
+                    if !value.is_empty() {
+                        tokens.push(Token {
+                            t: TokenType::String,
+                            value: std::mem::take(value),
+                            location: location.clone(),
+                        });
+                    }
+                };
+
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Err(ZephyrError {
+                            code: ErrorCode::UnterminatedString,
+                            message: String::from("Template string not closed"),
+                            location: Some(start_location),
+                        });
+                    };
+
+                    match c {
+                        '`' => {
+                            flush(&mut value, &value_location, &mut template_tokens);
+                            template_tokens.push(Token {
+                                t: TokenType::TemplateEnd,
+                                value: String::from("`"),
+                                location: Location {
+                                    start: current_char,
+                                    end: current_char + 1,
+                                    line: current_line,
+                                    file_name: Some(file_name.clone()),
+                                },
+                            });
+                            current_char += 1;
+                            break;
+                        }
+                        '\\' => {
+                            let Some(to_escape) = chars.next() else {
+                                return Err(ZephyrError {
+                                    code: ErrorCode::CannotEscape,
+                                    message: String::from("Nothing to escape"),
+                                    location: Some(start_location),
+                                });
+                            };
+
+                            let location = Location {
+                                start: current_char,
+                                end: current_char + 2,
+                                line: current_line,
+                                file_name: Some(file_name.clone()),
+                            };
+                            let (result, length) = match to_escape {
+                                '`' => ('`', 0),
+                                '{' => ('{', 0),
+                                '}' => ('}', 0),
+                                _ => escape(to_escape, &mut chars, &location)?,
+                            };
+                            value.push(result);
+                            current_char += 2 + length;
+                            value_location.end = current_char;
+                        }
+                        '{' => {
+                            flush(&mut value, &value_location, &mut template_tokens);
+                            template_tokens.push(Token {
+                                t: TokenType::TemplateExprStart,
+                                value: String::from("{"),
+                                location: Location {
+                                    start: current_char,
+                                    end: current_char + 1,
+                                    line: current_line,
+                                    file_name: Some(file_name.clone()),
+                                },
+                            });
+                            current_char += 1;
+
+                            // Collect the source of the expression, skipping over braces in strings
+                            let mut segment = String::new();
+                            let mut depth = 1;
+                            let mut in_string = false;
+                            loop {
+                                let Some(c) = chars.next() else {
+                                    return Err(ZephyrError {
+                                        code: ErrorCode::UnterminatedString,
+                                        message: String::from("Template expression not closed"),
+                                        location: Some(start_location),
+                                    });
+                                };
+
+                                match c {
+                                    '\\' if in_string => {
+                                        segment.push(c);
+                                        if let Some(next) = chars.next() {
+                                            segment.push(next);
+                                        }
+                                        continue;
+                                    }
+                                    '"' => in_string = !in_string,
+                                    '{' if !in_string => depth += 1,
+                                    '}' if !in_string => {
+                                        depth -= 1;
+                                        if depth == 0 {
+                                            break;
+                                        }
+                                    }
+                                    _ => (),
+                                }
+
+                                segment.push(c);
+                            }
+
+                            // Lex the expression on its own, then move it to where it is in the file
+                            let (segment_line, segment_char) = (current_line, current_char);
+                            let move_location = |location: &mut Location| {
+                                if location.line == 0 {
+                                    location.start += segment_char;
+                                    location.end += segment_char;
+                                }
+                                location.line += segment_line;
+                            };
+
+                            let mut segment_tokens =
+                                lex(&segment, file_name.clone()).map_err(|mut e| {
+                                    if let Some(ref mut location) = e.location {
+                                        move_location(location);
+                                    }
+                                    e
+                                })?;
+                            segment_tokens.pop();
+
+                            for mut token in segment_tokens {
+                                move_location(&mut token.location);
+                                template_tokens.push(token);
+                            }
+
+                            match segment.rsplit_once('\n') {
+                                Some((before, after)) => {
+                                    current_line += before.matches('\n').count() + 1;
+                                    current_char = after.chars().count();
+                                }
+                                None => current_char += segment.chars().count(),
+                            }
+
+                            template_tokens.push(Token {
+                                t: TokenType::TemplateExprEnd,
+                                value: String::from("}"),
+                                location: Location {
+                                    start: current_char,
+                                    end: current_char + 1,
+                                    line: current_line,
+                                    file_name: Some(file_name.clone()),
+                                },
+                            });
+                            current_char += 1;
+
+                            value_location = Location {
+                                start: current_char,
+                                end: current_char,
+                                line: current_line,
+                                file_name: Some(file_name.clone()),
+                            };
+                        }
+                        '\n' => {
+                            value.push(c);
+                            current_line += 1;
+                            current_char = 0;
+                        }
+                        _ => {
+                            value.push(c);
+                            current_char += 1;
+                            value_location.end = current_char;
+                        }
+                    }
+                }
+
+                tokens.extend(template_tokens);
+                continue;
             }
 
             _ if char.is_alphabetic() || char == '_' => {
@@ -173,7 +534,10 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                     "break" => TokenType::Break,
 
                     "func" => TokenType::Function,
+                    "async" => TokenType::Async,
+                    "await" => TokenType::Await,
                     "return" => TokenType::Return,
+                    "yield" => TokenType::Yield,
                     "where" => TokenType::Where,
 
                     "if" => TokenType::If,
@@ -183,6 +547,8 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                     "debug" => TokenType::Debug,
 
                     "enum" => TokenType::Enum,
+                    "struct" | "class" => TokenType::Struct,
+                    "extends" => TokenType::Extends,
                     _ => TokenType::Symbol,
                 });
                 current_length = value.len();
@@ -201,6 +567,10 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                                 chars.next();
                                 actual_value = Some(String::from("..="));
                                 TokenType::RangeInclusive
+                            } else if chars.peek().copied().unwrap_or('n') == '.' {
+                                chars.next();
+                                actual_value = Some(String::from("..."));
+                                TokenType::Spread
                             } else {
                                 actual_value = Some(String::from(".."));
                                 TokenType::Range
@@ -223,6 +593,10 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                             chars.next();
                             actual_value = Some(String::from("?."));
                             TokenType::DotOptional
+                        } else if next_char == '?' {
+                            chars.next();
+                            actual_value = Some(String::from("??"));
+                            TokenType::Coalesce
                         } else {
                             TokenType::QuestionMark
                         }
@@ -233,6 +607,12 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                             chars.next();
                             actual_value = Some(String::from("++"));
                             TokenType::Unary(Unary::Increment)
+                        } else if next_char == '=' {
+                            chars.next();
+                            actual_value = Some(String::from("+="));
+                            TokenType::AssignOperator(Box::from(TokenType::Additive(
+                                Additive::Plus,
+                            )))
                         } else {
                             TokenType::Additive(Additive::Plus)
                         }
@@ -246,6 +626,12 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                             chars.next();
                             actual_value = Some(String::from("->"));
                             TokenType::Arrow
+                        } else if next_char == '=' {
+                            chars.next();
+                            actual_value = Some(String::from("-="));
+                            TokenType::AssignOperator(Box::from(TokenType::Additive(
+                                Additive::Minus,
+                            )))
                         } else {
                             TokenType::Additive(Additive::Minus)
                         }
@@ -254,14 +640,36 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                     '*' => {
                         if next_char == '*' {
                             chars.next();
-                            actual_value = Some(String::from("**"));
-                            TokenType::Multiplicative(Multiplicative::Exponent)
+                            if chars.next_if_eq(&'=').is_some() {
+                                actual_value = Some(String::from("**="));
+                                TokenType::AssignOperator(Box::from(TokenType::Multiplicative(
+                                    Multiplicative::Exponent,
+                                )))
+                            } else {
+                                actual_value = Some(String::from("**"));
+                                TokenType::Multiplicative(Multiplicative::Exponent)
+                            }
+                        } else if next_char == '=' {
+                            chars.next();
+                            actual_value = Some(String::from("*="));
+                            TokenType::AssignOperator(Box::from(TokenType::Multiplicative(
+                                Multiplicative::Multiply,
+                            )))
                         } else {
                             TokenType::Multiplicative(Multiplicative::Multiply)
                         }
                     }
-                    '/' => TokenType::Multiplicative(Multiplicative::Divide),
-                    '%' => TokenType::Multiplicative(Multiplicative::Modulo),
+                    '%' => {
+                        if next_char == '=' {
+                            chars.next();
+                            actual_value = Some(String::from("%="));
+                            TokenType::AssignOperator(Box::from(TokenType::Multiplicative(
+                                Multiplicative::Modulo,
+                            )))
+                        } else {
+                            TokenType::Multiplicative(Multiplicative::Modulo)
+                        }
+                    }
 
                     '!' => {
                         if next_char == '=' {
@@ -284,6 +692,7 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
                         actual_value = Some(String::from("||"));
                         TokenType::Logical(Logical::Or)
                     }
+                    '|' => TokenType::Pipe,
 
                     '=' => {
                         if next_char == '=' {
@@ -357,3 +766,128 @@ pub fn lex(contents: &str, file_name: String) -> Result<Vec<Token>, ZephyrError>
 
     Ok(tokens)
 }
+
+/// Parses the contents of a number literal, e.g. 1_000, 3.14, 1e-9, 0xFF, 0b1010 or 0o17
+pub fn parse_number(value: &str) -> Result<f64, String> {
+    let (radix, digits) = match value.get(..2) {
+        Some("0x" | "0X") => (16, &value[2..]),
+        Some("0b" | "0B") => (2, &value[2..]),
+        Some("0o" | "0O") => (8, &value[2..]),
+        _ => (10, value),
+    };
+
+    // Underscores can only go between two digits
+    let chars: Vec<char> = digits.chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '_'
+            && !(i > 0
+                && chars[i - 1].is_digit(radix)
+                && chars.get(i + 1).is_some_and(|x| x.is_digit(radix)))
+        {
+            return Err(format!("Misplaced _ in number literal {}", value));
+        }
+    }
+
+    let digits = digits.replace('_', "");
+
+    if radix != 10 {
+        if digits.is_empty() {
+            return Err(format!("Expected digits after {}", value));
+        }
+
+        if let Some(c) = digits.chars().find(|x| !x.is_digit(radix)) {
+            return Err(format!(
+                "Invalid digit {} for base {} in {}",
+                c, radix, value
+            ));
+        }
+
+        return u64::from_str_radix(&digits, radix)
+            .map(|x| x as f64)
+            .map_err(|_| format!("Number literal {} is too large", value));
+    }
+
+    if let Some(c) = digits
+        .chars()
+        .find(|x| !x.is_ascii_digit() && !matches!(x, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return Err(format!(
+            "Invalid character {} in number literal {}",
+            c, value
+        ));
+    }
+
+    digits
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid number literal {}", value))
+}
+
+/// Gets the character that the escape sequence \c stands for, reading any extra characters it needs
+/// (\x41, \u{1F600}) from chars. Gives back the character and how many extra characters were read
+fn escape(
+    c: char,
+    chars: &mut Peekable<Chars>,
+    location: &Location,
+) -> Result<(char, usize), ZephyrError> {
+    let error = |message: String, length: usize| ZephyrError {
+        code: ErrorCode::CannotEscape,
+        message,
+        location: Some(Location {
+            end: location.end + length,
+            ..location.clone()
+        }),
+    };
+
+    Ok(match c {
+        '"' => ('"', 0),
+        '\\' => ('\\', 0),
+        'n' => ('\n', 0),
+        't' => ('\t', 0),
+        'r' => ('\r', 0),
+        '0' => ('\0', 0),
+        'x' => {
+            let digits: String = (0..2)
+                .filter_map(|_| chars.next_if(|x| x.is_ascii_hexdigit()))
+                .collect();
+            match u8::from_str_radix(&digits, 16) {
+                Ok(code) if digits.len() == 2 && code <= 0x7F => (code as char, 2),
+                _ => {
+                    return Err(error(
+                        String::from("\\x must be followed by 2 hex digits, up to 7F"),
+                        digits.len(),
+                    ))
+                }
+            }
+        }
+        'u' => {
+            if chars.next_if_eq(&'{').is_none() {
+                return Err(error(String::from("Expected { after \\u"), 0));
+            }
+
+            let digits: String =
+                std::iter::from_fn(|| chars.next_if(|x| x.is_ascii_hexdigit())).collect();
+            let length = digits.len() + 2;
+
+            if chars.next_if_eq(&'}').is_none() {
+                return Err(error(
+                    String::from("Expected } to close \\u{...}"),
+                    length - 1,
+                ));
+            }
+
+            match u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(result) if digits.len() <= 6 => (result, length),
+                _ => {
+                    return Err(error(
+                        format!("\\u{{{}}} is not a valid unicode character", digits),
+                        length,
+                    ))
+                }
+            }
+        }
+        _ => return Err(error(format!("Cannot escape {}", c), 0)),
+    })
+}
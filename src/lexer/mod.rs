@@ -3,7 +3,7 @@ pub mod tokens;
 
 #[cfg(test)]
 mod test {
-    use crate::lexer::tokens::TokenType;
+    use crate::lexer::tokens::{Multiplicative, TokenType, Unary};
 
     use super::lexer::lex;
 
@@ -60,4 +60,110 @@ mod test {
         let result = lex("test!", String::new()).unwrap();
         assert_eq!(result[0].value, "test!");
     }
+
+    #[test]
+    fn spread() {
+        let result = lex("...x ..=", String::new()).unwrap();
+        assert!(matches!(result[0].t, TokenType::Spread));
+        assert_eq!(result[1].value, "x");
+        assert!(matches!(result[2].t, TokenType::RangeInclusive));
+    }
+
+    #[test]
+    fn template_string() {
+        let result = lex("`a {b}`", String::new()).unwrap();
+        assert!(matches!(result[0].t, TokenType::TemplateStart));
+        assert_eq!(result[1].value, "a ");
+        assert!(matches!(result[2].t, TokenType::TemplateExprStart));
+        assert_eq!(result[3].value, "b");
+        assert_eq!(result[3].location.start, 4);
+        assert!(matches!(result[4].t, TokenType::TemplateExprEnd));
+        assert!(matches!(result[5].t, TokenType::TemplateEnd));
+    }
+
+    #[test]
+    fn number_formats() {
+        let result = lex("3.14 1e9 0xFF 1_000 1..2", String::new()).unwrap();
+        assert_eq!(result[0].value, "3.14");
+        assert_eq!(result[1].value, "1e9");
+        assert_eq!(result[2].value, "0xFF");
+        assert_eq!(result[3].value, "1_000");
+        assert_eq!(result[3].location.end, 19);
+        assert_eq!(result[4].value, "1");
+        assert!(matches!(result[5].t, TokenType::Range));
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        for number in ["1_", "0x", "0b12", "12abc", "1e"] {
+            let result = lex(number, String::new());
+            assert!(result.is_err(), "Expected {} to be invalid", number);
+        }
+    }
+
+    #[test]
+    fn string_escape_codes() {
+        let result = lex(r#""\x41\u{e9}\0""#, String::new()).unwrap();
+        assert_eq!(result[0].value, "A\u{e9}\0");
+        assert_eq!(result[0].location.end, 14);
+    }
+
+    #[test]
+    fn multi_line_strings() {
+        let result = lex("\"\"\"\na\n\"b\" \"\"\" x", String::new()).unwrap();
+        assert_eq!(result[0].value, "a\n\"b\" ");
+        assert_eq!(result[1].location.line, 2);
+        assert_eq!(result[1].location.start, 8);
+    }
+
+    #[test]
+    fn raw_strings() {
+        let result = lex(r###"r"\n" r#"a "quote""#"###, String::new()).unwrap();
+        assert_eq!(result[0].value, r"\n");
+        assert_eq!(result[1].value, r#"a "quote""#);
+    }
+
+    #[test]
+    fn assign_operators() {
+        let result = lex("a += 1 **= ++ /=", String::new()).unwrap();
+        assert!(matches!(result[1].t, TokenType::AssignOperator(_)));
+        assert_eq!(result[1].value, "+=");
+        assert_eq!(result[3].value, "**=");
+        assert!(matches!(result[4].t, TokenType::Unary(Unary::Increment)));
+        assert_eq!(result[5].value, "/=");
+    }
+
+    #[test]
+    fn optional_and_coalesce() {
+        let result = lex("a?.b ?? c ? d", String::new()).unwrap();
+        assert!(matches!(result[1].t, TokenType::DotOptional));
+        assert!(matches!(result[3].t, TokenType::Coalesce));
+        assert_eq!(result[3].value, "??");
+        assert!(matches!(result[5].t, TokenType::QuestionMark));
+    }
+
+    #[test]
+    fn pipe_and_or() {
+        let result = lex("1 | 2 || 3", String::new()).unwrap();
+        assert!(matches!(result[1].t, TokenType::Pipe));
+        assert!(matches!(result[3].t, TokenType::Logical(_)));
+    }
+
+    #[test]
+    fn struct_keywords() {
+        let result = lex("struct A extends B class", String::new()).unwrap();
+        assert!(matches!(result[0].t, TokenType::Struct));
+        assert!(matches!(result[2].t, TokenType::Extends));
+        assert!(matches!(result[4].t, TokenType::Struct));
+    }
+
+    #[test]
+    fn generator_tokens() {
+        let result = lex("func* a() { yield 1; }", String::new()).unwrap();
+        assert!(matches!(
+            result[1].t,
+            TokenType::Multiplicative(Multiplicative::Multiply)
+        ));
+        assert!(matches!(result[6].t, TokenType::Yield));
+    }
 }
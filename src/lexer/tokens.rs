@@ -24,6 +24,7 @@ pub enum TokenType {
     Arrow,
     Range,
     RangeInclusive,
+    Spread,
 
     Eof,
 
@@ -31,7 +32,16 @@ pub enum TokenType {
     String,
     Symbol,
 
+    TemplateStart,
+    TemplateExprStart,
+    TemplateExprEnd,
+    TemplateEnd,
+
     Assign,
+    Pipe,
+    Coalesce,
+    /// An arithmetic operator followed by =, like +=
+    AssignOperator(Box<TokenType>),
 
     Additive(Additive),
     Multiplicative(Multiplicative),
@@ -60,7 +70,10 @@ pub enum TokenType {
     Continue,
 
     Function,
+    Async,
+    Await,
     Return,
+    Yield,
     Where,
 
     If,
@@ -68,6 +81,8 @@ pub enum TokenType {
     Match,
 
     Enum,
+    Struct,
+    Extends,
 
     Debug,
 }
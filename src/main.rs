@@ -3,14 +3,17 @@
 use errors::ZephyrError;
 use lexer::lexer::lex;
 use parser::Parser;
-use runtime::{values::RuntimeValue, Interpreter};
-use std::{env, fs, thread};
+use runtime::{
+    profiler::Profiler, values::RuntimeValue, Interpreter, DEFAULT_MAX_CALL_DEPTH, STACK_PER_CALL,
+};
+use std::{cell::RefCell, env, fs, rc::Rc, thread};
 use std::io::{ErrorKind, Read, Write};
 use std::net::TcpStream;
 use std::sync::mpsc;
 use std::time::Duration;
 use crate::runtime::values::thread_crossing::{ThreadInnerValue, ThreadRuntimeValue};
 
+mod compiler;
 mod errors;
 mod lexer;
 mod parser;
@@ -18,12 +21,61 @@ mod runtime;
 mod util;
 
 fn main() {
-    let args = env::args().collect::<Vec<String>>();
+    // Flags such as --vm can go anywhere, everything else is positional
+    let (flags, args): (Vec<String>, Vec<String>) = env::args().partition(|x| x.starts_with("--"));
+    let mut use_vm = false;
+    let mut profile = None;
+    let mut max_call_depth = DEFAULT_MAX_CALL_DEPTH;
 
+    for flag in &flags {
+        match flag.as_str() {
+            "--vm" => use_vm = true,
+            // --profile writes the folded stacks to profile.folded, or to the path after --profile=
+            "--profile" => profile = Some("profile.folded".to_string()),
+            x if x.starts_with("--profile=") => profile = Some(x["--profile=".len()..].to_string()),
+            x if x.starts_with("--max-call-depth=") => {
+                let x = &x["--max-call-depth=".len()..];
+                max_call_depth = x.parse::<usize>().unwrap_or_else(|_| {
+                    eprintln!("--max-call-depth expects a number, got {}", x);
+                    std::process::exit(1)
+                })
+            }
+            x => {
+                eprintln!(
+                    "Unknown flag {}, the flags are --vm, --profile[=path] and --max-call-depth=n",
+                    x
+                );
+                std::process::exit(1)
+            }
+        }
+    }
+
+    // Each Zephyr call takes a lot of Rust stack, so the interpreter gets a thread with enough of
+    // it to reach the maximum call depth rather than overflowing
+    let interpreter_thread = thread::Builder::new()
+        .stack_size(
+            max_call_depth
+                .saturating_mul(STACK_PER_CALL)
+                .max(8 * 1024 * 1024),
+        )
+        .spawn(move || start(args, use_vm, profile, max_call_depth))
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to make a stack big enough for a call depth of {}: {}",
+                max_call_depth, err
+            );
+            std::process::exit(1)
+        });
+    if interpreter_thread.join().is_err() {
+        std::process::exit(1);
+    }
+}
+
+fn start(args: Vec<String>, use_vm: bool, profile: Option<String>, max_call_depth: usize) {
     if let Some(file_name) = args.get(2) {
         println!(
             "{}",
-            match run(&file_name) {
+            match run(&file_name, use_vm, profile, max_call_depth) {
                 Ok(ok) => ok.to_string(true, true, true).unwrap_or_else(|err| err.visualise()),
 
                 Err(err) => err.visualise(),
@@ -31,6 +83,7 @@ fn main() {
         );
     } else {
         let mut interpreter = Interpreter::new("<REPL>".to_string());
+        interpreter.max_call_depth = max_call_depth;
         loop {
             let mut line = String::new();
             std::io::stdin().read_line(&mut line).unwrap();
@@ -45,13 +98,17 @@ fn main() {
                 },
                 "<REPL>".to_string(),
             );
-            let parsed = match parser.produce_ast() {
+            let mut parsed = match parser.produce_ast() {
                 Ok(ok) => ok,
                 Err(err) => {
                     eprintln!("{}", err.visualise());
                     continue;
                 }
             };
+            if let Err(err) = Interpreter::resolve(&mut parsed, &interpreter.scope) {
+                eprintln!("{}", err.visualise());
+                continue;
+            }
             match interpreter.run(parsed) {
                 Ok(ok) => println!("{}", ok.to_string(true, true, true).unwrap()),
                 Err(err) => eprintln!("{}", err.visualise()),
@@ -60,10 +117,21 @@ fn main() {
     }
 }
 
-fn run(file_name: &str) -> Result<RuntimeValue, ZephyrError> {
+fn run(
+    file_name: &str,
+    use_vm: bool,
+    profile: Option<String>,
+    max_call_depth: usize,
+) -> Result<RuntimeValue, ZephyrError> {
     let data = fs::read_to_string(file_name).unwrap();
 
     let result = lex(&data, file_name.to_string())?;
-    let parsed = Parser::new(result, String::from(file_name.to_string())).produce_ast()?;
-    Interpreter::new(fs::canonicalize(file_name).unwrap().display().to_string()).base_run(parsed)
+    let mut parsed = Parser::new(result, String::from(file_name.to_string())).produce_ast()?;
+    let mut interpreter =
+        Interpreter::new(fs::canonicalize(file_name).unwrap().display().to_string());
+    Interpreter::resolve(&mut parsed, &interpreter.scope)?;
+    interpreter.use_vm = use_vm;
+    interpreter.max_call_depth = max_call_depth;
+    interpreter.profiler = profile.map(|x| Rc::new(RefCell::new(Profiler::new(x))));
+    interpreter.base_run(parsed)
 }
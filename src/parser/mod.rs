@@ -5,20 +5,34 @@ use std::{
 
 use either::Either::{Left, Right};
 use nodes::{
-    DeclareType, ExposeType, InterruptType, MatchCase, MatchCaseType, Node, TaggedSymbol, UnaryType,
+    CatchType, DeclareType, ExposeType, InterruptType, MatchCase, MatchCaseType, MatchPattern,
+    Node, TaggedSymbol, UnaryType,
 };
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
-    lexer::tokens::{self, Token, TokenType, Unary, NO_LOCATION},
+    lexer::{
+        lexer::parse_number,
+        tokens::{self, Location, Token, TokenType, Unary, NO_LOCATION},
+    },
 };
 
 type NR = Result<Node, ZephyrError>;
 
 pub mod nodes;
+pub mod resolver;
 pub struct Parser {
     pub tokens: Vec<Token>,
     pub _file_name: String,
+    /// Whether the current function is a generator, so yield can be used
+    pub in_generator: bool,
+    /// Whether the next statement is directly in a generator's body, or in the blocks, ifs and
+    /// loops within it, as those are the only places a generator can stop at a yield
+    pub yield_statement: bool,
+    /// How many yields have been parsed, to find ones inside of a larger expression
+    pub yields: usize,
+    /// Whether await can be used, which is at the top level and inside of async functions
+    pub in_async: bool,
 }
 
 impl Parser {
@@ -47,6 +61,7 @@ impl Parser {
     pub fn make_symbol(token: Token) -> nodes::Symbol {
         nodes::Symbol {
             value: token.value,
+            address: None,
             location: token.location,
         }
     }
@@ -55,6 +70,10 @@ impl Parser {
         Parser {
             tokens,
             _file_name: file_name,
+            in_generator: false,
+            yield_statement: false,
+            yields: 0,
+            in_async: true,
         }
     }
 
@@ -64,6 +83,7 @@ impl Parser {
 
     pub fn block(&mut self, no_brace: bool) -> NR {
         let mut nodes: Vec<Node> = vec![];
+        let yield_statement = std::mem::replace(&mut self.yield_statement, false);
 
         let mut uses_arrow = false;
         let open_token = if !no_brace {
@@ -87,12 +107,14 @@ impl Parser {
         };
 
         if uses_arrow {
+            self.yield_statement = yield_statement;
             nodes.push(self.statement()?);
         } else {
             while !self.tokens.is_empty()
                 && !matches!(self.at().t, TokenType::CloseBrace)
                 && !matches!(self.at().t, TokenType::Eof)
             {
+                self.yield_statement = yield_statement;
                 nodes.push(self.statement()?);
 
                 if discriminant(&TokenType::Semicolon) == discriminant(&self.at().t) {
@@ -125,26 +147,67 @@ impl Parser {
     }
 
     pub fn statement(&mut self) -> NR {
+        // Anything inside of the statement can not yield, unless it is the body of a loop, if or block
+        let yield_statement = std::mem::replace(&mut self.yield_statement, false);
+
         match self.at().t {
             TokenType::Let | TokenType::Const => self.declare(),
             TokenType::Enum => self.enum_stmt(),
+            TokenType::Struct => self.struct_stmt(),
             TokenType::Function => self.function(true),
+            TokenType::Async
+                if matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::Function)) =>
+            {
+                self.function(true)
+            }
             TokenType::Debug => Ok(Node::Debug(nodes::DebugNode {
                 location: self.eat().location.clone(),
                 node: Box::from(self.expression()?),
             })),
             TokenType::Export => self.export(),
             TokenType::Import => self.import(),
-            TokenType::While => self.while_stmt(),
-            TokenType::For => self.for_loop(),
-            TokenType::Continue => Ok(Node::Interrupt(nodes::Interrupt {
-                location: self.eat().location,
-                t: InterruptType::Continue,
-            })),
-            TokenType::Break => Ok(Node::Interrupt(nodes::Interrupt {
-                location: self.eat().location,
-                t: InterruptType::Break,
-            })),
+            TokenType::While => {
+                self.yield_statement = yield_statement;
+                self.while_stmt(None)
+            }
+            TokenType::For => {
+                self.yield_statement = yield_statement;
+                self.for_loop(None)
+            }
+            // label: for ... / label: while ...
+            TokenType::Symbol
+                if matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::Colon))
+                    && matches!(
+                        self.tokens.get(2).map(|x| &x.t),
+                        Some(TokenType::For | TokenType::While)
+                    ) =>
+            {
+                let label = Parser::make_symbol(self.eat());
+                self.eat();
+
+                self.yield_statement = yield_statement;
+                if matches!(self.at().t, TokenType::For) {
+                    self.for_loop(Some(label))
+                } else {
+                    self.while_stmt(Some(label))
+                }
+            }
+            TokenType::Continue => {
+                let token = self.eat();
+                Ok(Node::Interrupt(nodes::Interrupt {
+                    t: InterruptType::Continue(self.interrupt_label(&token)),
+                    location: token.location,
+                    tail_call: false,
+                }))
+            }
+            TokenType::Break => {
+                let token = self.eat();
+                Ok(Node::Interrupt(nodes::Interrupt {
+                    t: InterruptType::Break(self.interrupt_label(&token)),
+                    location: token.location,
+                    tail_call: false,
+                }))
+            }
             TokenType::Return => {
                 let token = self.eat();
                 let value = if let TokenType::Semicolon = self.at().t {
@@ -156,12 +219,78 @@ impl Parser {
                 Ok(Node::Interrupt(nodes::Interrupt {
                     location: token.location,
                     t: InterruptType::Return(value),
+                    tail_call: false,
                 }))
             }
+            TokenType::Yield => {
+                let token = self.eat();
+                if !self.in_generator {
+                    return Err(ZephyrError {
+                        message: "yield can only be used inside of a generator (func*)".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(token.location),
+                    });
+                }
+                if !yield_statement {
+                    return Err(Parser::misplaced_yield(token.location));
+                }
+                self.yields += 1;
+
+                let value = if let TokenType::Semicolon = self.at().t {
+                    None
+                } else {
+                    Some(Box::from(self.expression()?))
+                };
+
+                Ok(Node::Yield(nodes::Yield {
+                    value,
+                    location: token.location,
+                }))
+            }
+            // An if or block on its own can yield, but not if it is part of a larger expression
+            TokenType::If | TokenType::OpenBrace if yield_statement => {
+                let yields = self.yields;
+                self.yield_statement = true;
+                let node = self.expression()?;
+
+                if self.yields != yields && !matches!(node, Node::If(_) | Node::Block(_)) {
+                    return Err(Parser::misplaced_yield(node.location().clone()));
+                }
+                Ok(node)
+            }
             _ => self.expression(),
         }
     }
 
+    fn misplaced_yield(location: Location) -> ZephyrError {
+        ZephyrError {
+            message: "yield can only be used as a statement of a generator's body, or of the blocks, ifs and loops within it".to_string(),
+            code: ErrorCode::UnexpectedToken,
+            location: Some(location),
+        }
+    }
+
+    /// The label after break / continue, it must be on the same line
+    pub fn interrupt_label(&mut self, token: &Token) -> Option<nodes::Symbol> {
+        if matches!(self.at().t, TokenType::Symbol)
+            && self.at().location.line == token.location.line
+        {
+            Some(Parser::make_symbol(self.eat()))
+        } else {
+            None
+        }
+    }
+
+    /// The else block after a loop, which runs if the loop was broken out of
+    pub fn loop_else(&mut self) -> Result<Option<Box<Node>>, ZephyrError> {
+        if matches!(self.at().t, TokenType::Else) {
+            self.eat();
+            Ok(Some(Box::from(self.block(false)?)))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn enum_stmt(&mut self) -> NR {
         let token = self.eat();
         let name = Parser::make_symbol(self.expect(
@@ -182,9 +311,20 @@ impl Parser {
             },
         )?;
 
-        let mut items: Vec<(nodes::Symbol, String)> = vec![];
+        let mut items: Vec<nodes::EnumItem> = vec![];
+        let mut methods: Vec<nodes::Function> = vec![];
+
+        while !matches!(self.at().t, TokenType::CloseBrace | TokenType::Eof) {
+            // Methods are added to the enum's prototype
+            if matches!(self.at().t, TokenType::Function | TokenType::Async) {
+                methods.push(self.method()?);
+
+                if matches!(self.at().t, TokenType::Comma) {
+                    self.eat();
+                }
+                continue;
+            }
 
-        while !matches!(self.at().t, TokenType::CloseBrace) {
             let symbol = Parser::make_symbol(self.expect(
                 discriminant(&TokenType::Symbol),
                 ZephyrError {
@@ -194,20 +334,18 @@ impl Parser {
                 },
             )?);
 
-            let symbol_clone = symbol.clone();
-            items.push((
-                symbol,
-                format!(
-                    "{}.{}__{}",
-                    name.value,
-                    symbol_clone.value,
-                    uuid::Uuid::new_v4()
-                ),
-            ));
+            let fields = self.field_list()?;
+
+            let id = format!("{}.{}__{}", name.value, symbol.value, uuid::Uuid::new_v4());
+            items.push(nodes::EnumItem {
+                name: symbol,
+                id,
+                fields,
+            });
 
             if matches!(self.at().t, TokenType::Comma) {
                 self.eat();
-            } else {
+            } else if !matches!(self.at().t, TokenType::Function | TokenType::Async) {
                 break;
             }
         }
@@ -224,6 +362,128 @@ impl Parser {
         Ok(Node::Enum(nodes::Enum {
             name,
             values: items,
+            methods,
+            location: token.location,
+        }))
+    }
+
+    /// Parses `(a, b, c)` if it is there, used for the shape of enum variants and structs
+    pub fn field_list(&mut self) -> Result<Option<Vec<nodes::Symbol>>, ZephyrError> {
+        if !matches!(self.at().t, TokenType::OpenParan) {
+            return Ok(None);
+        }
+
+        self.eat();
+        let mut fields: Vec<nodes::Symbol> = vec![];
+
+        while !matches!(self.at().t, TokenType::CloseParan | TokenType::Eof) {
+            let field = Parser::make_symbol(self.expect(
+                discriminant(&TokenType::Symbol),
+                ZephyrError {
+                    message: "Expected symbol for field".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(self.at().location.clone()),
+                },
+            )?);
+
+            if fields.iter().any(|x| x.value == field.value) {
+                return Err(ZephyrError {
+                    message: format!("Field {} is already defined", field.value),
+                    code: ErrorCode::AlreadyDefined,
+                    location: Some(field.location),
+                });
+            }
+            fields.push(field);
+
+            if matches!(self.at().t, TokenType::Comma) {
+                self.eat();
+            } else {
+                break;
+            }
+        }
+
+        self.expect(
+            discriminant(&TokenType::CloseParan),
+            ZephyrError {
+                message: "Expected close paran".to_string(),
+                code: ErrorCode::UnexpectedToken,
+                location: Some(self.at().location.clone()),
+            },
+        )?;
+
+        Ok(Some(fields))
+    }
+
+    /// A named function inside of an enum or struct body
+    pub fn method(&mut self) -> Result<nodes::Function, ZephyrError> {
+        let location = self.at().location.clone();
+        match self.function(false)? {
+            Node::Function(func) if func.name.is_some() => Ok(func),
+            _ => Err(ZephyrError {
+                message: "Expected name for method".to_string(),
+                code: ErrorCode::UnexpectedToken,
+                location: Some(location),
+            }),
+        }
+    }
+
+    pub fn struct_stmt(&mut self) -> NR {
+        let token = self.eat();
+        let name = Parser::make_symbol(self.expect(
+            discriminant(&TokenType::Symbol),
+            ZephyrError {
+                message: "Expected symbol for struct name".to_string(),
+                code: ErrorCode::UnexpectedToken,
+                location: Some(self.at().location.clone()),
+            },
+        )?);
+
+        let fields = self.field_list()?;
+
+        let extends = if matches!(self.at().t, TokenType::Extends) {
+            self.eat();
+            Some(Box::from(self.expression()?))
+        } else {
+            None
+        };
+
+        let mut methods: Vec<nodes::Function> = vec![];
+
+        // The body is optional, struct Point(x, y) is enough on its own
+        if matches!(self.at().t, TokenType::OpenBrace) {
+            self.eat();
+
+            while !matches!(self.at().t, TokenType::CloseBrace | TokenType::Eof) {
+                if !matches!(self.at().t, TokenType::Function | TokenType::Async) {
+                    return Err(ZephyrError {
+                        message: "Expected a method".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(self.at().location.clone()),
+                    });
+                }
+
+                methods.push(self.method()?);
+
+                if matches!(self.at().t, TokenType::Comma | TokenType::Semicolon) {
+                    self.eat();
+                }
+            }
+
+            self.expect(
+                discriminant(&TokenType::CloseBrace),
+                ZephyrError {
+                    message: "Expected close brace".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(self.at().location.clone()),
+                },
+            )?;
+        }
+
+        Ok(Node::Struct(nodes::Struct {
+            name,
+            fields,
+            extends,
+            methods,
             location: token.location,
         }))
     }
@@ -286,8 +546,9 @@ impl Parser {
             self.eat();
             loop {
                 let mut expr = match self.at().t {
-                    TokenType::Symbol => ExposeType::Identifier(self.eat().value),
+                    TokenType::Symbol => ExposeType::Identifier(Parser::make_symbol(self.eat())),
                     TokenType::Multiplicative(tokens::Multiplicative::Multiply) => {
+                        self.eat();
                         ExposeType::Star()
                     }
                     _ => {
@@ -337,6 +598,93 @@ impl Parser {
 
     pub fn export(&mut self) -> NR {
         let token = self.eat();
+
+        // export * from "x"
+        // export * as y from "x"
+        if matches!(
+            self.at().t,
+            TokenType::Multiplicative(tokens::Multiplicative::Multiply)
+        ) {
+            self.eat();
+            let expose = if matches!(self.at().t, TokenType::As) {
+                self.eat();
+                ExposeType::StarAs(self.export_as_symbol()?)
+            } else {
+                ExposeType::Star()
+            };
+
+            return Ok(Node::Export(nodes::Export {
+                export: nodes::ExportType::From(vec![expose], self.export_from()?),
+                location: token.location,
+                export_as: None,
+            }));
+        }
+
+        // export .{ a, b as c } (from "x")
+        if matches!(self.at().t, TokenType::Dot)
+            && matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::OpenBrace))
+        {
+            self.eat();
+            self.eat();
+
+            let mut items: Vec<(nodes::Symbol, Option<String>)> = vec![];
+            while !matches!(self.at().t, TokenType::CloseBrace) {
+                let symbol = Parser::make_symbol(self.expect(
+                    discriminant(&TokenType::Symbol),
+                    ZephyrError {
+                        message: "Expected symbol".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(self.at().location.clone()),
+                    },
+                )?);
+
+                let export_as = if matches!(self.at().t, TokenType::As) {
+                    self.eat();
+                    Some(self.export_as_symbol()?)
+                } else {
+                    None
+                };
+
+                items.push((symbol, export_as));
+
+                if matches!(self.at().t, TokenType::Comma) {
+                    self.eat();
+                } else {
+                    break;
+                }
+            }
+
+            self.expect(
+                discriminant(&TokenType::CloseBrace),
+                ZephyrError {
+                    message: "Expected close brace".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(self.at().location.clone()),
+                },
+            )?;
+
+            let export = if self.is_at_from() {
+                nodes::ExportType::From(
+                    items
+                        .into_iter()
+                        .map(|(symbol, export_as)| match export_as {
+                            Some(export_as) => ExposeType::IdentifierAs(symbol, export_as),
+                            None => ExposeType::Identifier(symbol),
+                        })
+                        .collect(),
+                    self.export_from()?,
+                )
+            } else {
+                nodes::ExportType::Object(items)
+            };
+
+            return Ok(Node::Export(nodes::Export {
+                export,
+                location: token.location,
+                export_as: None,
+            }));
+        }
+
         let node = self.statement()?;
 
         let t = match node {
@@ -353,21 +701,27 @@ impl Parser {
 
         let export_as = if matches!(self.at().t, TokenType::As) {
             self.eat();
-            Some(
-                self.expect(
-                    discriminant(&TokenType::Symbol),
-                    ZephyrError {
-                        message: "Expected symbol".to_string(),
-                        code: ErrorCode::UnexpectedToken,
-                        location: Some(self.at().location.clone()),
-                    },
-                )?
-                .value,
-            )
+            Some(self.export_as_symbol()?)
         } else {
             None
         };
 
+        // export a (as b) from "x"
+        if let nodes::ExportType::Symbol(ref symbol) = t {
+            if self.is_at_from() {
+                let expose = match export_as {
+                    Some(export_as) => ExposeType::IdentifierAs(symbol.clone(), export_as),
+                    None => ExposeType::Identifier(symbol.clone()),
+                };
+
+                return Ok(Node::Export(nodes::Export {
+                    export: nodes::ExportType::From(vec![expose], self.export_from()?),
+                    location: token.location,
+                    export_as: None,
+                }));
+            }
+        }
+
         Ok(Node::Export(nodes::Export {
             export: t,
             location: token.location,
@@ -375,77 +729,176 @@ impl Parser {
         }))
     }
 
+    fn export_as_symbol(&mut self) -> Result<String, ZephyrError> {
+        Ok(self
+            .expect(
+                discriminant(&TokenType::Symbol),
+                ZephyrError {
+                    message: "Expected symbol".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(self.at().location.clone()),
+                },
+            )?
+            .value)
+    }
+
+    /// from is not a keyword, so it can still be used as a variable name
+    fn is_at_from(&self) -> bool {
+        matches!(self.at().t, TokenType::Symbol) && self.at().value == "from"
+    }
+
+    fn export_from(&mut self) -> Result<String, ZephyrError> {
+        if !self.is_at_from() {
+            return Err(ZephyrError {
+                message: "Expected from".to_string(),
+                code: ErrorCode::UnexpectedToken,
+                location: Some(self.at().location.clone()),
+            });
+        }
+        self.eat();
+
+        Ok(self
+            .expect(
+                discriminant(&TokenType::String),
+                ZephyrError {
+                    message: "Expected string containing import location".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(self.at().location.clone()),
+                },
+            )?
+            .value)
+    }
+
     pub fn declare(&mut self) -> NR {
         let token = self.eat();
         let is_const = !matches!(token.t, TokenType::Let);
 
-        let symbol = match self.at().t {
-            TokenType::Symbol => DeclareType::Symbol(Parser::make_symbol(self.eat())),
+        let symbol = self.declare_pattern()?;
+
+        if let TokenType::Assign = self.at().t {
+            let assign = self.eat();
+            let value = self.expression()?;
+
+            Ok(Node::Declare(nodes::Declare {
+                assignee: symbol,
+                location: assign.location,
+                value: Some(Box::from(value)),
+                is_const,
+            }))
+        } else {
+            Ok(Node::Declare(nodes::Declare {
+                assignee: symbol,
+                location: token.location,
+                value: None,
+                is_const,
+            }))
+        }
+    }
+
+    // x
+    // [x, y]
+    // .{ x, y: z, w = default }
+    // with any of them nested inside the others
+    pub fn declare_pattern(&mut self) -> Result<DeclareType, ZephyrError> {
+        match self.at().t {
+            TokenType::Symbol => Ok(DeclareType::Symbol(Parser::make_symbol(self.eat()))),
             TokenType::OpenSquare => {
                 let _ = self.eat();
-                let mut names: Vec<nodes::Symbol> = vec![];
+                let mut parts: Vec<DeclareType> = vec![];
 
                 loop {
-                    let name = self.expect(
+                    parts.push(self.declare_pattern()?);
+
+                    if matches!(self.at().t, TokenType::Comma) {
+                        self.eat();
+                        continue;
+                    } else {
+                        break;
+                    }
+                }
+
+                self.expect(
+                    discriminant(&TokenType::CloseSquare),
+                    ZephyrError {
+                        message: "Expected close square paran".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(self.at().location.clone()),
+                    },
+                )?;
+
+                Ok(DeclareType::Array(parts))
+            }
+            TokenType::Dot => {
+                let _ = self.eat();
+                self.expect(
+                    discriminant(&TokenType::OpenBrace),
+                    ZephyrError {
+                        message: "Expected start of object pattern".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(self.at().location.clone()),
+                    },
+                )?;
+
+                let mut parts: Vec<nodes::ObjectDeclarePart> = vec![];
+
+                while !matches!(self.at().t, TokenType::CloseBrace) {
+                    let key = Parser::make_symbol(self.expect(
                         discriminant(&TokenType::Symbol),
                         ZephyrError {
                             message: "Expected symbol".to_string(),
                             code: ErrorCode::UnexpectedToken,
                             location: Some(self.at().location.clone()),
                         },
-                    )?;
+                    )?);
+
+                    let assignee = if matches!(self.at().t, TokenType::Colon) {
+                        self.eat();
+                        self.declare_pattern()?
+                    } else {
+                        DeclareType::Symbol(key.clone())
+                    };
 
-                    names.push(Parser::make_symbol(name));
+                    let default = if matches!(self.at().t, TokenType::Assign) {
+                        self.eat();
+                        Some(Box::from(self.expression()?))
+                    } else {
+                        None
+                    };
+
+                    parts.push(nodes::ObjectDeclarePart {
+                        key,
+                        assignee,
+                        default,
+                    });
 
                     if matches!(self.at().t, TokenType::Comma) {
                         self.eat();
-                        continue;
                     } else {
                         break;
                     }
                 }
 
                 self.expect(
-                    discriminant(&TokenType::CloseSquare),
+                    discriminant(&TokenType::CloseBrace),
                     ZephyrError {
-                        message: "Expected close square paran".to_string(),
+                        message: "Expected close brace".to_string(),
                         code: ErrorCode::UnexpectedToken,
                         location: Some(self.at().location.clone()),
                     },
                 )?;
 
-                DeclareType::Array(names)
-            }
-            _ => {
-                return Err(ZephyrError {
-                    message: "Cannot assign to this".to_string(),
-                    code: ErrorCode::UnexpectedToken,
-                    location: Some(self.at().location.clone()),
-                })
+                Ok(DeclareType::Object(parts))
             }
-        };
-
-        if let TokenType::Assign = self.at().t {
-            let assign = self.eat();
-            let value = self.expression()?;
-
-            Ok(Node::Declare(nodes::Declare {
-                assignee: symbol,
-                location: assign.location,
-                value: Some(Box::from(value)),
-                is_const,
-            }))
-        } else {
-            Ok(Node::Declare(nodes::Declare {
-                assignee: symbol,
-                location: token.location,
-                value: None,
-                is_const,
-            }))
+            _ => Err(ZephyrError {
+                message: "Cannot assign to this".to_string(),
+                code: ErrorCode::UnexpectedToken,
+                location: Some(self.at().location.clone()),
+            }),
         }
     }
 
-    pub fn for_loop(&mut self) -> NR {
+    pub fn for_loop(&mut self, label: Option<nodes::Symbol>) -> NR {
+        let yield_statement = std::mem::replace(&mut self.yield_statement, false);
         let token = self.eat();
         let index_symbol = Parser::make_symbol(self.expect(
             discriminant(&TokenType::Symbol),
@@ -481,9 +934,13 @@ impl Parser {
 
         let iterator = self.expression()?;
 
+        self.yield_statement = yield_statement;
         let block = self.block(false)?;
+        let on_break = self.loop_else()?;
 
         Ok(Node::For(nodes::For {
+            label,
+            on_break,
             value_symbol,
             index_symbol,
             location: token.location,
@@ -493,35 +950,137 @@ impl Parser {
     }
 
     pub fn function(&mut self, is_statement: bool) -> NR {
-        let token = self.eat();
+        // async func - a function giving a promise
+        let is_async = matches!(self.at().t, TokenType::Async);
+        if is_async {
+            self.eat();
+        }
+
+        let token = self.expect(
+            discriminant(&TokenType::Function),
+            ZephyrError {
+                message: "Expected func".to_string(),
+                code: ErrorCode::UnexpectedToken,
+                location: None,
+            },
+        )?;
+
+        // func* - a generator
+        let is_generator = matches!(
+            self.at().t,
+            TokenType::Multiplicative(tokens::Multiplicative::Multiply)
+        );
+        if is_generator {
+            let token = self.eat();
+            if is_async {
+                return Err(ZephyrError {
+                    message: "A function cannot be both async and a generator".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(token.location),
+                });
+            }
+        }
+
+        let name = {
+            if let TokenType::Symbol = self.at().t {
+                Some(self.eat())
+            } else if is_statement {
+                return Err(ZephyrError {
+                    code: ErrorCode::UnexpectedToken,
+                    message: "Expected name for function".to_string(),
+                    location: Some(self.at().location.clone()),
+                });
+            } else {
+                None
+            }
+        };
+
+        let mut arguments = nodes::Arguments::default();
+
+        if let TokenType::OpenParan = self.at().t {
+            self.eat();
+            let mut in_named = false;
+
+            while !matches!(self.at().t, TokenType::CloseParan) {
+                let is_rest_taken = if in_named {
+                    arguments.named_rest.is_some()
+                } else {
+                    arguments.positional_rest.is_some()
+                };
+
+                // % - the start of the named arguments
+                if matches!(
+                    self.at().t,
+                    TokenType::Multiplicative(tokens::Multiplicative::Modulo)
+                ) {
+                    if in_named {
+                        return Err(ZephyrError {
+                            code: ErrorCode::UnexpectedToken,
+                            message: "Named arguments have already been started".to_string(),
+                            location: Some(self.at().location.clone()),
+                        });
+                    }
+
+                    self.eat();
+                    in_named = true;
+                } else if is_rest_taken {
+                    return Err(ZephyrError {
+                        code: ErrorCode::UnexpectedToken,
+                        message: "Nothing can come after a rest argument, other than %".to_string(),
+                        location: Some(self.at().location.clone()),
+                    });
+                } else if matches!(self.at().t, TokenType::Spread) {
+                    self.eat();
+                    let symbol = Some(Parser::make_symbol(self.expect(
+                        discriminant(&TokenType::Symbol),
+                        ZephyrError {
+                            code: ErrorCode::UnexpectedToken,
+                            message: "Expected symbol for rest argument".to_string(),
+                            location: Some(self.at().location.clone()),
+                        },
+                    )?));
+
+                    if in_named {
+                        arguments.named_rest = symbol;
+                    } else {
+                        arguments.positional_rest = symbol;
+                    }
+                } else {
+                    let name = Parser::make_symbol(self.expect(
+                        discriminant(&TokenType::Symbol),
+                        ZephyrError {
+                            code: ErrorCode::UnexpectedToken,
+                            message: "Expected symbol".to_string(),
+                            location: Some(self.at().location.clone()),
+                        },
+                    )?);
 
-        let name = {
-            if let TokenType::Symbol = self.at().t {
-                Some(self.eat())
-            } else if is_statement {
-                return Err(ZephyrError {
-                    code: ErrorCode::UnexpectedToken,
-                    message: "Expected name for function".to_string(),
-                    location: Some(self.at().location.clone()),
-                });
-            } else {
-                None
-            }
-        };
+                    let default = if matches!(self.at().t, TokenType::Assign) {
+                        self.eat();
+                        Some(Box::from(self.expression()?))
+                    } else {
+                        None
+                    };
 
-        let mut arguments: Vec<nodes::Symbol> = vec![];
+                    if in_named {
+                        arguments.named.push(nodes::Argument { name, default });
+                    } else {
+                        if default.is_none()
+                            && arguments.positional.iter().any(|x| x.default.is_some())
+                        {
+                            return Err(ZephyrError {
+                                code: ErrorCode::UnexpectedToken,
+                                message: format!(
+                                    "Argument {} must have a default value as it comes after one that does",
+                                    name.value
+                                ),
+                                location: Some(name.location),
+                            });
+                        }
 
-        if let TokenType::OpenParan = self.at().t {
-            self.eat();
-            while !matches!(self.at().t, TokenType::CloseParan) {
-                arguments.push(Parser::make_symbol(self.expect(
-                    discriminant(&TokenType::Symbol),
-                    ZephyrError {
-                        code: ErrorCode::UnexpectedToken,
-                        message: "Expected symbol".to_string(),
-                        location: Some(self.at().location.clone()),
-                    },
-                )?));
+                        arguments.positional.push(nodes::Argument { name, default });
+                    }
+                }
 
                 if matches!(self.at().t, TokenType::Comma) {
                     self.eat();
@@ -540,7 +1099,13 @@ impl Parser {
             )?;
         }
 
-        let block = self.block(false)?;
+        let outer_generator = std::mem::replace(&mut self.in_generator, is_generator);
+        let outer_async = std::mem::replace(&mut self.in_async, is_async);
+        self.yield_statement = is_generator;
+        let block = self.block(false);
+        self.in_generator = outer_generator;
+        self.in_async = outer_async;
+        let block = block?;
 
         let function = Node::Function(nodes::Function {
             name: name.as_ref().map(|x| Parser::make_symbol(x.clone())),
@@ -549,6 +1114,8 @@ impl Parser {
                 _ => unreachable!(),
             },
             args: arguments,
+            is_generator,
+            is_async,
 
             location: token.location.clone(),
         });
@@ -565,14 +1132,79 @@ impl Parser {
         }
     }
 
-    pub fn while_stmt(&mut self) -> NR {
+    pub fn try_expr(&mut self) -> NR {
+        let token = self.eat();
+        let attempt = self.expression()?;
+
+        let catch = if matches!(self.at().t, TokenType::Catch) {
+            self.eat();
+
+            // catch return
+            // catch e return z
+            // catch e { ... } / catch e -> ...
+            // catch y
+            if matches!(self.at().t, TokenType::Return) {
+                self.eat();
+                Some(CatchType::Return)
+            } else if matches!(self.at().t, TokenType::Symbol)
+                && matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::Return))
+            {
+                // The same as catch e { return z }
+                let symbol = Parser::make_symbol(self.eat());
+                let token = self.eat();
+                let value = self.expression()?;
+
+                Some(CatchType::Binding(
+                    symbol,
+                    Box::from(Node::Interrupt(nodes::Interrupt {
+                        t: InterruptType::Return(Some(Box::from(value))),
+                        location: token.location,
+                        tail_call: false,
+                    })),
+                ))
+            } else if matches!(self.at().t, TokenType::Symbol)
+                && matches!(
+                    self.tokens.get(1).map(|x| &x.t),
+                    Some(TokenType::OpenBrace) | Some(TokenType::Arrow)
+                )
+            {
+                let symbol = Parser::make_symbol(self.eat());
+                Some(CatchType::Binding(symbol, Box::from(self.block(false)?)))
+            } else {
+                Some(CatchType::Value(Box::from(self.expression()?)))
+            }
+        } else {
+            None
+        };
+
+        let finally = if matches!(self.at().t, TokenType::Finally) {
+            self.eat();
+            Some(Box::from(self.block(false)?))
+        } else {
+            None
+        };
+
+        Ok(Node::Try(nodes::Try {
+            attempt: Box::from(attempt),
+            catch,
+            finally,
+            location: token.location,
+        }))
+    }
+
+    pub fn while_stmt(&mut self, label: Option<nodes::Symbol>) -> NR {
+        let yield_statement = std::mem::replace(&mut self.yield_statement, false);
         let token = self.eat();
 
         let test = self.expression()?;
 
+        self.yield_statement = yield_statement;
         let block = self.block(false)?;
+        let on_break = self.loop_else()?;
 
         Ok(Node::WhileLoop(nodes::WhileLoop {
+            label,
+            on_break,
             test: Box::from(test),
             body: Box::from(block),
             location: token.location,
@@ -580,13 +1212,16 @@ impl Parser {
     }
 
     pub fn if_stmt(&mut self) -> NR {
+        let yield_statement = std::mem::replace(&mut self.yield_statement, false);
         let token = self.eat();
 
         let test = self.expression()?;
+        self.yield_statement = yield_statement;
         let success = self.block(false)?;
 
         let alternate = if let TokenType::Else = self.at().t {
             self.eat();
+            self.yield_statement = yield_statement;
             if let TokenType::If = self.at().t {
                 Some(Box::from(self.if_stmt()?))
             } else {
@@ -626,29 +1261,18 @@ impl Parser {
                 let block = self.block(false)?;
 
                 MatchCaseType::Else(Box::from(block))
-            } else if let TokenType::Comparison(c) = self.at().t.clone() {
-                let _ = self.eat();
-
-                let value = self.expression()?;
-                let block = self.block(false)?;
-
-                MatchCaseType::MatchCase(MatchCase {
-                    op: c,
-                    value: Box::from(value),
-                    success: Box::from(block),
-                })
-            } else if let TokenType::Is = self.at().t {
-                let result = match self.is(Some(test.clone()))? {
-                    Node::Is(is) => is,
-                    _ => unreachable!(),
+            } else {
+                let pattern = self.match_pattern(false)?;
+                let guard = if matches!(self.at().t, TokenType::Where) {
+                    self.eat();
+                    Some(Box::from(self.expression()?))
+                } else {
+                    None
                 };
-                let block = self.block(false)?;
 
-                MatchCaseType::Is(Box::from(result.left), result.right, Box::from(block))
-            } else {
                 MatchCaseType::MatchCase(MatchCase {
-                    op: tokens::Comparison::Eq,
-                    value: Box::from(self.expression()?),
+                    pattern,
+                    guard,
                     success: Box::from(self.block(false)?),
                 })
             };
@@ -678,6 +1302,155 @@ impl Parser {
         }))
     }
 
+    /// A pattern for a match case, nested is set inside of array and object patterns
+    /// where a symbol binds the value rather than comparing with it
+    pub fn match_pattern(&mut self, nested: bool) -> Result<MatchPattern, ZephyrError> {
+        let mut alternatives = vec![self.match_pattern_single(nested)?];
+
+        while matches!(self.at().t, TokenType::Pipe) {
+            self.eat();
+            alternatives.push(self.match_pattern_single(nested)?);
+        }
+
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => MatchPattern::Alternatives(alternatives),
+        })
+    }
+
+    fn match_pattern_single(&mut self, nested: bool) -> Result<MatchPattern, ZephyrError> {
+        match self.at().t.clone() {
+            TokenType::Comparison(c) => {
+                self.eat();
+                Ok(MatchPattern::Comparison(c, Box::from(self.expression()?)))
+            }
+            TokenType::Is => {
+                self.eat();
+                let right = self.comparison()?;
+                let binding = if matches!(self.at().t, TokenType::As) {
+                    self.eat();
+                    Some(Parser::make_symbol(self.expect(
+                        discriminant(&TokenType::Symbol),
+                        ZephyrError {
+                            message: "Expected symbol to bind to".to_string(),
+                            code: ErrorCode::UnexpectedToken,
+                            location: Some(self.at().location.clone()),
+                        },
+                    )?))
+                } else {
+                    None
+                };
+
+                Ok(MatchPattern::Is(
+                    nodes::IsType::Basic(Box::from(right)),
+                    binding,
+                ))
+            }
+            TokenType::OpenSquare => {
+                self.eat();
+                let mut items: Vec<MatchPattern> = vec![];
+                let mut rest: Option<nodes::Symbol> = None;
+
+                while !matches!(self.at().t, TokenType::CloseSquare | TokenType::Eof) {
+                    if matches!(self.at().t, TokenType::Spread) {
+                        self.eat();
+                        rest = Some(Parser::make_symbol(self.expect(
+                            discriminant(&TokenType::Symbol),
+                            ZephyrError {
+                                message: "Expected symbol for the rest of the array".to_string(),
+                                code: ErrorCode::UnexpectedToken,
+                                location: Some(self.at().location.clone()),
+                            },
+                        )?));
+                        break;
+                    }
+
+                    items.push(self.match_pattern(true)?);
+
+                    if matches!(self.at().t, TokenType::Comma) {
+                        self.eat();
+                    } else {
+                        break;
+                    }
+                }
+
+                self.expect(
+                    discriminant(&TokenType::CloseSquare),
+                    ZephyrError {
+                        message: "Expected end of array pattern, the rest must be last".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(self.at().location.clone()),
+                    },
+                )?;
+
+                Ok(MatchPattern::Array(items, rest))
+            }
+            TokenType::Dot
+                if matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::OpenBrace)) =>
+            {
+                self.eat();
+                self.eat();
+                let mut items: Vec<(nodes::Symbol, MatchPattern)> = vec![];
+
+                while !matches!(self.at().t, TokenType::CloseBrace | TokenType::Eof) {
+                    let key = Parser::make_symbol(self.expect(
+                        discriminant(&TokenType::Symbol),
+                        ZephyrError {
+                            message: "Expected key".to_string(),
+                            code: ErrorCode::UnexpectedToken,
+                            location: Some(self.at().location.clone()),
+                        },
+                    )?);
+
+                    let pattern = if matches!(self.at().t, TokenType::Colon) {
+                        self.eat();
+                        self.match_pattern(true)?
+                    } else {
+                        MatchPattern::Binding(key.clone())
+                    };
+
+                    items.push((key, pattern));
+
+                    if matches!(self.at().t, TokenType::Comma) {
+                        self.eat();
+                    } else {
+                        break;
+                    }
+                }
+
+                self.expect(
+                    discriminant(&TokenType::CloseBrace),
+                    ZephyrError {
+                        message: "Expected end of object pattern".to_string(),
+                        code: ErrorCode::UnexpectedToken,
+                        location: Some(self.at().location.clone()),
+                    },
+                )?;
+
+                Ok(MatchPattern::Object(items))
+            }
+            // A lone symbol binds, but a.b or a() is still a value to compare with
+            TokenType::Symbol
+                if nested
+                    && !matches!(
+                        self.tokens.get(1).map(|x| &x.t),
+                        Some(
+                            TokenType::Dot
+                                | TokenType::DotOptional
+                                | TokenType::OpenParan
+                                | TokenType::OpenSquare
+                        )
+                    ) =>
+            {
+                Ok(MatchPattern::Binding(Parser::make_symbol(self.eat())))
+            }
+            _ => Ok(MatchPattern::Comparison(
+                tokens::Comparison::Eq,
+                Box::from(self.expression()?),
+            )),
+        }
+    }
+
     pub fn expression(&mut self) -> NR {
         self.assign()
     }
@@ -685,13 +1458,20 @@ impl Parser {
     pub fn assign(&mut self) -> NR {
         let left = self.logical()?;
 
-        if let TokenType::Assign = self.at().t {
+        if matches!(
+            self.at().t,
+            TokenType::Assign | TokenType::AssignOperator(_)
+        ) {
             let token = self.eat();
             let value = self.expression()?;
 
             return Ok(Node::Assign(nodes::Assign {
                 assignee: Box::from(left),
                 value: Box::from(value),
+                operator: match token.t {
+                    TokenType::AssignOperator(operator) => Some(*operator),
+                    _ => None,
+                },
                 location: token.location,
             }));
         }
@@ -700,7 +1480,7 @@ impl Parser {
     }
 
     pub fn logical(&mut self) -> NR {
-        let left = self.is(None)?;
+        let left = self.coalesce()?;
 
         if matches!(self.at().t, TokenType::Logical(_)) {
             let token = self.eat();
@@ -720,6 +1500,22 @@ impl Parser {
         return Ok(left);
     }
 
+    pub fn coalesce(&mut self) -> NR {
+        let mut left = self.is(None)?;
+
+        while matches!(self.at().t, TokenType::Coalesce) {
+            let token = self.eat();
+            let right = self.is(None)?;
+            left = Node::Coalesce(nodes::Coalesce {
+                left: Box::from(left),
+                right: Box::from(right),
+                location: token.location,
+            })
+        }
+
+        Ok(left)
+    }
+
     pub fn is(&mut self, base: Option<Node>) -> NR {
         let left = if let Some(base) = base {
             base
@@ -877,7 +1673,7 @@ impl Parser {
             let t = match token.t {
                 TokenType::Unary(Unary::Length) => UnaryType::LengthOf,
                 TokenType::Unary(Unary::Not) => UnaryType::Not,
-                TokenType::Unary(Unary::Decrement) => UnaryType::Increment,
+                TokenType::Unary(Unary::Decrement) => UnaryType::Decrement,
                 TokenType::Unary(Unary::Increment) => UnaryType::Increment,
                 TokenType::Additive(tokens::Additive::Plus) => UnaryType::Plus,
                 TokenType::Additive(tokens::Additive::Minus) => UnaryType::Minus,
@@ -894,18 +1690,37 @@ impl Parser {
             }));
         }
 
+        // await x - waits for the promise x to be settled
+        if matches!(self.at().t, TokenType::Await) {
+            let token = self.eat();
+            if !self.in_async {
+                return Err(ZephyrError {
+                    message: "await can only be used at the top level or inside of an async func"
+                        .to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                    location: Some(token.location),
+                });
+            }
+
+            return Ok(Node::Await(nodes::Await {
+                value: Box::from(self.unary()?),
+                location: token.location,
+            }));
+        }
+
         let value = self.call_member_new()?;
 
-        if matches!(self.at().t, TokenType::Unary(_)) {
+        // x++ / x--
+        if matches!(
+            self.at().t,
+            TokenType::Unary(Unary::Increment | Unary::Decrement)
+        ) {
             let token = self.eat();
             let t = match token.t {
-                TokenType::Unary(Unary::Decrement) => UnaryType::Increment,
-                TokenType::Unary(Unary::Increment) => UnaryType::Increment,
-                _ => unreachable!(),
+                TokenType::Unary(Unary::Decrement) => UnaryType::Decrement,
+                _ => UnaryType::Increment,
             };
 
-            let value = self.call_member_new()?;
-
             return Ok(Node::Unary(nodes::Unary {
                 t,
                 value: Box::from(value),
@@ -942,16 +1757,49 @@ impl Parser {
         };
 
         while check(self.at().clone()) {
-            // Check for left()
-            if matches!(self.at().t, TokenType::OpenParan) {
+            // Check for left() or left?.()
+            let optional_call = matches!(self.at().t, TokenType::DotOptional)
+                && matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::OpenParan));
+
+            if optional_call || matches!(self.at().t, TokenType::OpenParan) {
+                if optional_call {
+                    self.eat();
+                }
                 let token = self.eat();
                 let mut arguments: Vec<Node> = vec![];
+                let mut named_arguments: Vec<(nodes::Symbol, Node)> = vec![];
 
                 // Collect arguments
                 while !matches!(self.at().t, TokenType::CloseParan)
                     && !matches!(self.at().t, TokenType::Eof)
                 {
-                    arguments.push(self.expression()?);
+                    // name: value
+                    if matches!(self.at().t, TokenType::Symbol)
+                        && matches!(self.tokens.get(1).map(|x| &x.t), Some(TokenType::Colon))
+                    {
+                        let name = Parser::make_symbol(self.eat());
+                        self.eat();
+
+                        if named_arguments.iter().any(|(x, _)| x.value == name.value) {
+                            return Err(ZephyrError {
+                                code: ErrorCode::UnexpectedToken,
+                                message: format!("Named argument {} was given twice", name.value),
+                                location: Some(name.location),
+                            });
+                        }
+
+                        named_arguments.push((name, self.expression()?));
+                    } else if !named_arguments.is_empty() {
+                        return Err(ZephyrError {
+                            code: ErrorCode::UnexpectedToken,
+                            message: "Positional arguments cannot come after named arguments"
+                                .to_string(),
+                            location: Some(self.at().location.clone()),
+                        });
+                    } else {
+                        arguments.push(self.spreadable()?);
+                    }
+
                     if let TokenType::Comma = self.at().t {
                         self.eat();
                     } else {
@@ -973,6 +1821,8 @@ impl Parser {
                 left = Node::Call(nodes::Call {
                     left: Box::from(left),
                     args: arguments,
+                    named_args: named_arguments,
+                    optional: optional_call,
                     location: token.location,
                 });
             } else {
@@ -1039,12 +1889,12 @@ impl Parser {
         match self.at().t {
             TokenType::Number => {
                 let pre_value = self.eat();
-                let value = match pre_value.value.parse::<f64>() {
+                let value = match parse_number(&pre_value.value) {
                     Ok(ok) => ok,
                     Err(err) => {
                         return Err(ZephyrError {
                             code: ErrorCode::InvalidNumber,
-                            message: format!("Failed to parse number: {}", err.to_string()),
+                            message: format!("Failed to parse number: {}", err),
                             location: Some(pre_value.location),
                         })
                     }
@@ -1061,16 +1911,24 @@ impl Parser {
                     location: token.location,
                 }))
             }
+            TokenType::TemplateStart => self.template_string(),
             TokenType::Symbol => {
                 let token = self.eat();
-                Ok(Node::Symbol(nodes::Symbol {
-                    value: token.value,
-                    location: token.location,
-                }))
+                Ok(Node::Symbol(Parser::make_symbol(token)))
             }
-            TokenType::Function => self.function(false),
+            TokenType::Function | TokenType::Async => self.function(false),
             TokenType::If => self.if_stmt(),
             TokenType::Match => self.match_stmt(),
+            TokenType::Try => self.try_expr(),
+            TokenType::Throw => {
+                let token = self.eat();
+                let value = self.expression()?;
+
+                Ok(Node::Throw(nodes::Throw {
+                    value: Box::from(value),
+                    location: token.location,
+                }))
+            }
             TokenType::OpenSquare => {
                 let token = self.eat();
                 let mut items: Vec<Node> = vec![];
@@ -1078,7 +1936,7 @@ impl Parser {
                 while !matches!(self.at().t, TokenType::Eof)
                     && !matches!(self.at().t, TokenType::CloseSquare)
                 {
-                    items.push(self.expression()?);
+                    items.push(self.spreadable()?);
                     if let TokenType::Comma = self.at().t {
                         self.eat();
                         continue;
@@ -1112,34 +1970,42 @@ impl Parser {
                     },
                 )?;
 
-                let mut items: HashMap<String, TaggedSymbol> = HashMap::new();
+                let mut items: Vec<nodes::ObjectItem> = vec![];
 
                 while !matches!(self.at().t, TokenType::Eof)
                     && !matches!(self.at().t, TokenType::CloseBrace)
                 {
-                    let identifier = Parser::make_symbol(self.expect(
-                        discriminant(&TokenType::Symbol),
-                        ZephyrError {
-                            code: ErrorCode::UnexpectedToken,
-                            message: "Expected identifier".to_string(),
-                            location: Some(self.at().location.clone()),
-                        },
-                    )?);
-
-                    let value = if let TokenType::Colon = self.at().t {
-                        self.eat();
-                        self.expression()?
+                    if matches!(self.at().t, TokenType::Spread) {
+                        let token = self.eat();
+                        items.push(nodes::ObjectItem::Spread(nodes::Spread {
+                            value: Box::from(self.expression()?),
+                            location: token.location,
+                        }));
                     } else {
-                        Node::Symbol(identifier.clone())
-                    };
+                        let identifier = Parser::make_symbol(self.expect(
+                            discriminant(&TokenType::Symbol),
+                            ZephyrError {
+                                code: ErrorCode::UnexpectedToken,
+                                message: "Expected identifier".to_string(),
+                                location: Some(self.at().location.clone()),
+                            },
+                        )?);
 
-                    items.insert(
-                        identifier.value,
-                        TaggedSymbol {
-                            value: Box::from(value),
-                            tags: HashMap::new(),
-                        },
-                    );
+                        let value = if let TokenType::Colon = self.at().t {
+                            self.eat();
+                            self.expression()?
+                        } else {
+                            Node::Symbol(identifier.clone())
+                        };
+
+                        items.push(nodes::ObjectItem::Property(
+                            identifier.value,
+                            TaggedSymbol {
+                                value: Box::from(value),
+                                tags: HashMap::new(),
+                            },
+                        ));
+                    }
 
                     if let TokenType::Comma = self.at().t {
                         self.eat();
@@ -1186,4 +2052,67 @@ impl Parser {
             }
         }
     }
+    /// An expression that can be spread with ..., used in arrays and call arguments
+    pub fn spreadable(&mut self) -> NR {
+        if matches!(self.at().t, TokenType::Spread) {
+            let token = self.eat();
+            return Ok(Node::Spread(nodes::Spread {
+                value: Box::from(self.expression()?),
+                location: token.location,
+            }));
+        }
+
+        self.expression()
+    }
+
+    pub fn template_string(&mut self) -> NR {
+        let token = self.eat();
+        let mut parts: Vec<Node> = vec![];
+
+        loop {
+            match self.at().t {
+                TokenType::String => {
+                    let part = self.eat();
+                    parts.push(Node::ZString(nodes::ZString {
+                        value: part.value,
+                        location: part.location,
+                    }));
+                }
+                TokenType::TemplateExprStart => {
+                    self.eat();
+                    parts.push(self.expression()?);
+                    self.expect(
+                        discriminant(&TokenType::TemplateExprEnd),
+                        ZephyrError {
+                            code: ErrorCode::UnexpectedToken,
+                            message: format!(
+                                "Expected end of template expression, but got {}",
+                                self.at().value
+                            ),
+                            location: Some(self.at().location.clone()),
+                        },
+                    )?;
+                }
+                TokenType::TemplateEnd => {
+                    self.eat();
+                    break;
+                }
+                _ => {
+                    return Err(ZephyrError {
+                        code: ErrorCode::UnexpectedToken,
+                        message: format!(
+                            "Cannot handle the token {} in a template string",
+                            self.at().value
+                        ),
+                        location: Some(self.at().location.clone()),
+                    })
+                }
+            }
+        }
+
+        Ok(Node::TemplateString(nodes::TemplateString {
+            parts,
+            location: token.location,
+        }))
+    }
 }
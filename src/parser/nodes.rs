@@ -15,24 +15,32 @@ pub enum Node {
     Import(Import),
     Interrupt(Interrupt),
     Match(Match),
+    Throw(Throw),
+    Try(Try),
     WhileLoop(WhileLoop),
+    Yield(Yield),
 
     Arithmetic(Arithmetic),
+    Await(Await),
     Call(Call),
+    Coalesce(Coalesce),
     Comp(Comp),
     Debug(DebugNode),
     Enum(Enum),
+    Struct(Struct),
     Function(Function),
     Is(Is),
     Logical(Logical),
     Member(Member),
     Range(Range),
+    Spread(Spread),
     Unary(Unary),
 
     Array(Array),
     Number(Number),
     Symbol(Symbol),
     Object(Object),
+    TemplateString(TemplateString),
     ZString(ZString),
 }
 
@@ -50,24 +58,32 @@ impl Node {
             Node::Import(v) => &v.location,
             Node::Interrupt(v) => &v.location,
             Node::Match(v) => &v.location,
+            Node::Throw(v) => &v.location,
+            Node::Try(v) => &v.location,
             Node::WhileLoop(v) => &v.location,
+            Node::Yield(v) => &v.location,
 
             Node::Arithmetic(v) => &v.location,
+            Node::Await(v) => &v.location,
             Node::Call(v) => &v.location,
+            Node::Coalesce(v) => &v.location,
             Node::Comp(v) => &v.location,
             Node::Debug(v) => &v.location,
             Node::Enum(v) => &v.location,
+            Node::Struct(v) => &v.location,
             Node::Function(v) => &v.location,
             Node::Is(v) => &v.location,
             Node::Logical(v) => &v.location,
             Node::Member(v) => &v.location,
             Node::Range(v) => &v.location,
+            Node::Spread(v) => &v.location,
             Node::Unary(v) => &v.location,
 
             Node::Array(v) => &v.location,
             Node::Number(v) => &v.location,
             Node::Object(v) => &v.location,
             Node::Symbol(v) => &v.location,
+            Node::TemplateString(v) => &v.location,
             Node::ZString(v) => &v.location,
         }
     }
@@ -97,6 +113,13 @@ pub struct ZString {
     pub location: Location,
 }
 
+/// `a {b} c`, parts are ZStrings for the text and any other node for an embedded expression
+#[derive(Debug, Clone)]
+pub struct TemplateString {
+    pub parts: Vec<Node>,
+    pub location: Location,
+}
+
 #[derive(Debug, Clone)]
 pub struct Array {
     pub items: Vec<Node>,
@@ -109,26 +132,68 @@ pub struct TaggedSymbol {
     pub tags: HashMap<String, String>,
 }
 
+#[derive(Debug, Clone)]
+pub enum ObjectItem {
+    Property(String, TaggedSymbol),
+    Spread(Spread),
+}
+
 #[derive(Debug, Clone)]
 pub struct Object {
-    pub items: HashMap<String, TaggedSymbol>,
+    /// In order, so later items override earlier ones
+    pub items: Vec<ObjectItem>,
     pub location: Location,
 }
 
+/// ...value inside of an array, object or call arguments
+#[derive(Debug, Clone)]
+pub struct Spread {
+    pub value: Box<Node>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Argument {
+    pub name: Symbol,
+    pub default: Option<Box<Node>>,
+}
+
+/// (positional args[, ...rest][, %, named args[, ...named_rest]])
+#[derive(Debug, Clone, Default)]
+pub struct Arguments {
+    pub positional: Vec<Argument>,
+    pub positional_rest: Option<Symbol>,
+    pub named: Vec<Argument>,
+    pub named_rest: Option<Symbol>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub name: Option<Symbol>,
     pub body: Block,
-    pub args: Vec<Symbol>,
+    pub args: Arguments,
+    /// func* - calling it gives a generator rather than running the body
+    pub is_generator: bool,
+    /// async func - calling it gives a promise of the result
+    pub is_async: bool,
     pub location: Location,
 }
 
 #[derive(Debug, Clone)]
 pub struct Symbol {
     pub value: String,
+    /// Where the variable is, set by the resolver when the symbol is used as a variable
+    pub address: Option<Address>,
     pub location: Location,
 }
 
+/// A variable's place in the scopes, depth is how many scopes up it is and slot is its index there
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Address {
+    pub depth: usize,
+    pub slot: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct Arithmetic {
     pub left: Box<Node>,
@@ -145,6 +210,13 @@ pub struct Comp {
     pub location: Location,
 }
 
+#[derive(Debug, Clone)]
+pub struct Coalesce {
+    pub left: Box<Node>,
+    pub right: Box<Node>,
+    pub location: Location,
+}
+
 #[derive(Debug, Clone)]
 pub struct Logical {
     pub left: Box<Node>,
@@ -157,6 +229,9 @@ pub struct Logical {
 pub struct Call {
     pub left: Box<Node>,
     pub args: Vec<Node>,
+    pub named_args: Vec<(Symbol, Node)>,
+    /// a?.() gives null rather than calling when a is null
+    pub optional: bool,
     pub location: Location,
 }
 
@@ -169,11 +244,18 @@ pub struct Member {
     pub location: Location,
 }
 
+#[derive(Debug, Clone)]
+pub struct ObjectDeclarePart {
+    pub key: Symbol,
+    pub assignee: DeclareType,
+    pub default: Option<Box<Node>>,
+}
+
 #[derive(Debug, Clone)]
 pub enum DeclareType {
     Symbol(Symbol),
-    Array(Vec<Symbol>),
-    Object(HashMap<String, String>),
+    Array(Vec<DeclareType>),
+    Object(Vec<ObjectDeclarePart>),
 }
 
 #[derive(Debug, Clone)]
@@ -188,6 +270,8 @@ pub struct Declare {
 pub struct Assign {
     pub assignee: Box<Node>,
     pub value: Box<Node>,
+    /// The arithmetic operator for compound assignments like +=
+    pub operator: Option<TokenType>,
     pub location: Location,
 }
 
@@ -201,8 +285,11 @@ pub struct If {
 
 #[derive(Debug, Clone)]
 pub struct WhileLoop {
+    pub label: Option<Symbol>,
     pub test: Box<Node>,
     pub body: Box<Node>,
+    /// The else block, ran only if the loop was broken out of
+    pub on_break: Option<Box<Node>>,
     pub location: Location,
 }
 
@@ -210,13 +297,29 @@ pub struct WhileLoop {
 pub enum MatchCaseType {
     MatchCase(MatchCase),
     Else(Box<Node>),
-    Is(Box<Node>, IsType, Box<Node>),
+}
+
+#[derive(Debug, Clone)]
+pub enum MatchPattern {
+    /// `op x`, or just `x` which is `== x`
+    Comparison(Comparison, Box<Node>),
+    /// `is x [as y]` - y is bound to the inner value of an enum variant, or the value itself
+    Is(IsType, Option<Symbol>),
+    /// A symbol inside of an array or object pattern, binds whatever is there
+    Binding(Symbol),
+    /// `[a, b, ...rest]`
+    Array(Vec<MatchPattern>, Option<Symbol>),
+    /// `.{ key: pattern, key }` - the object must have the keys, other keys are allowed
+    Object(Vec<(Symbol, MatchPattern)>),
+    /// `a | b`
+    Alternatives(Vec<MatchPattern>),
 }
 
 #[derive(Debug, Clone)]
 pub struct MatchCase {
-    pub op: Comparison,
-    pub value: Box<Node>,
+    pub pattern: MatchPattern,
+    /// `pattern where x` - x is ran with the bindings and must be truthy for the case to match
+    pub guard: Option<Box<Node>>,
     pub success: Box<Node>,
 }
 
@@ -233,11 +336,39 @@ pub struct DebugNode {
     pub location: Location,
 }
 
+#[derive(Debug, Clone)]
+pub enum CatchType {
+    /// `catch y` - provides y instead
+    Value(Box<Node>),
+    /// `catch e { ... }` - runs the block with the error bound to e
+    /// `catch e return z` is parsed as `catch e { return z }`
+    Binding(Symbol, Box<Node>),
+    /// `catch return` - returns Result.Err from the surrounding function
+    Return,
+}
+
+#[derive(Debug, Clone)]
+pub struct Try {
+    pub attempt: Box<Node>,
+    pub catch: Option<CatchType>,
+    pub finally: Option<Box<Node>>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Throw {
+    pub value: Box<Node>,
+    pub location: Location,
+}
+
 #[derive(Debug, Clone)]
 pub enum ExportType {
     Symbol(Symbol),
     Declaration(Declare),
-    Object(Object),
+    /// export .{ a, b as c }
+    Object(Vec<(Symbol, Option<String>)>),
+    /// export a from "x", export .{ a, b as c } from "x", export * from "x"
+    From(Vec<ExposeType>, String),
 }
 
 #[derive(Debug, Clone)]
@@ -249,21 +380,36 @@ pub struct Export {
 
 #[derive(Debug, Clone)]
 pub enum InterruptType {
-    Continue,
-    Break,
+    Continue(Option<Symbol>),
+    Break(Option<Symbol>),
     Return(Option<Box<Node>>),
 }
 
+#[derive(Debug, Clone)]
+pub struct Yield {
+    pub value: Option<Box<Node>>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Await {
+    pub value: Box<Node>,
+    pub location: Location,
+}
+
 #[derive(Debug, Clone)]
 pub struct Interrupt {
     pub location: Location,
     pub t: InterruptType,
+    /// Set by the resolver when this returns a call which the function can make in place of
+    /// calling it from here
+    pub tail_call: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum ExposeType {
-    Identifier(String),
-    IdentifierAs(String, String),
+    Identifier(Symbol),
+    IdentifierAs(Symbol, String),
     Star(),
     StarAs(String),
 }
@@ -277,10 +423,13 @@ pub struct Import {
 
 #[derive(Debug, Clone)]
 pub struct For {
+    pub label: Option<Symbol>,
     pub value_symbol: Option<Symbol>,
     pub index_symbol: Symbol,
     pub iterator: Box<Node>,
     pub block: Box<Node>,
+    /// The else block, ran only if the loop was broken out of
+    pub on_break: Option<Box<Node>>,
     pub location: Location,
 }
 
@@ -328,6 +477,25 @@ pub struct Is {
 #[derive(Debug, Clone)]
 pub struct Enum {
     pub name: Symbol,
-    pub values: Vec<(Symbol, String)>,
+    pub values: Vec<EnumItem>,
+    pub methods: Vec<Function>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub name: Symbol,
+    /// The fields filled by the constructor when there is no init method
+    pub fields: Option<Vec<Symbol>>,
+    pub extends: Option<Box<Node>>,
+    pub methods: Vec<Function>,
     pub location: Location,
 }
+
+#[derive(Debug, Clone)]
+pub struct EnumItem {
+    pub name: Symbol,
+    pub id: String,
+    /// The payload shape, `None` if declared without brackets
+    pub fields: Option<Vec<Symbol>>,
+}
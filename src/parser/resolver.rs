@@ -0,0 +1,854 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    lexer::tokens::{Comparison, Location},
+};
+
+use super::nodes::{
+    self, Address, CatchType, DeclareType, ExportType, ExposeType, InterruptType, IsType,
+    MatchCaseType, MatchPattern, Node, ObjectItem,
+};
+
+/// A scope the interpreter will create, with the variables it will have
+#[derive(Default)]
+struct ResolverScope {
+    /// The slot of each variable, which is the order they are declared in, and whether the
+    /// declaration has been reached yet
+    slots: HashMap<String, (usize, bool)>,
+    /// Whether the scope gets variables which are only known when it runs, from import *
+    is_dynamic: bool,
+    /// Whether this is the scope of a function's arguments, anything outside of it has been
+    /// declared by the time the function is called
+    is_function: bool,
+    /// The variant names of the enums declared in the scope, to check matches over them
+    enums: HashMap<String, Vec<String>>,
+}
+
+/// Gives every variable used an address, so the interpreter does not have to search for it by name
+///
+/// The scopes here have to be the same as the ones the interpreter creates. Every declaration in a
+/// block is known before anything in it is resolved, so functions can use variables declared after
+/// them, but other code only sees the ones it comes after, like when it runs
+pub struct Resolver {
+    scopes: Vec<ResolverScope>,
+    /// Whether a returned call can be made by the function instead, which is not the case outside
+    /// of functions, in generators, or where a try would need to catch what it throws
+    tail_calls: bool,
+    /// Problems which do not stop the code from running, such as a match missing enum variants
+    pub warnings: Vec<ZephyrError>,
+}
+
+impl Resolver {
+    /// Creates a resolver for a node ran in the given scopes, each one being the names of its variables
+    /// in slot order, from the outermost scope to the current one
+    pub fn new(scopes: Vec<Vec<String>>) -> Self {
+        Resolver {
+            scopes: scopes
+                .into_iter()
+                .map(|names| ResolverScope {
+                    slots: names
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, x)| (x, (i, true)))
+                        .collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            tail_calls: false,
+            warnings: vec![],
+        }
+    }
+
+    pub fn resolve(&mut self, node: &mut Node) -> Result<(), ZephyrError> {
+        self.node(node)
+    }
+
+    fn push(&mut self) {
+        self.scopes.push(ResolverScope::default());
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Gives the symbol the next slot of the current scope, defined is whether it can be used yet
+    fn declare(&mut self, symbol: &nodes::Symbol, defined: bool) -> Result<(), ZephyrError> {
+        // _ is never stored, see Scope::insert
+        if symbol.value == "_" {
+            return Ok(());
+        }
+
+        let scope = self.scopes.last_mut().expect("The resolver has no scope");
+        if scope.slots.contains_key(&symbol.value) {
+            return Err(ZephyrError {
+                code: ErrorCode::AlreadyDefined,
+                message: format!(
+                    "Variable {} already exists in the current scope",
+                    symbol.value
+                ),
+                location: Some(symbol.location.clone()),
+            });
+        }
+
+        let slot = scope.slots.len();
+        scope.slots.insert(symbol.value.clone(), (slot, defined));
+        Ok(())
+    }
+
+    /// Marks a declared variable as reached, so code after it can use it
+    fn define(&mut self, symbol: &nodes::Symbol) {
+        let scope = self.scopes.last_mut().expect("The resolver has no scope");
+        if let Some((_, defined)) = scope.slots.get_mut(&symbol.value) {
+            *defined = true;
+        }
+    }
+
+    /// Gives a used symbol the address of the variable it refers to
+    fn reference(&mut self, symbol: &mut nodes::Symbol) -> Result<(), ZephyrError> {
+        let mut in_function = false;
+
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            // The slots of a dynamic scope are not known, so it has to be looked up by name
+            if scope.is_dynamic {
+                return Ok(());
+            }
+
+            // A variable declared later in the block is not there yet, so an outer one is used
+            if let Some((slot, defined)) = scope.slots.get(&symbol.value) {
+                if *defined || in_function {
+                    symbol.address = Some(Address { depth, slot: *slot });
+                    return Ok(());
+                }
+            }
+
+            in_function |= scope.is_function;
+        }
+
+        Err(ZephyrError {
+            code: ErrorCode::UnknownReference,
+            message: format!("Cannot find variable {} in the current scope", symbol.value),
+            location: Some(symbol.location.clone()),
+        })
+    }
+
+    /// Declares everything the statements declare in the current scope, then resolves them
+    fn statements(&mut self, nodes: &mut [Node]) -> Result<(), ZephyrError> {
+        for node in nodes.iter() {
+            self.hoist(node)?;
+        }
+
+        for node in nodes {
+            self.node(node)?;
+        }
+
+        Ok(())
+    }
+
+    fn hoist(&mut self, node: &Node) -> Result<(), ZephyrError> {
+        match node {
+            Node::Declare(expr) => self.declare_pattern(&expr.assignee),
+            Node::Export(nodes::Export {
+                export: ExportType::Declaration(expr),
+                ..
+            }) => self.declare_pattern(&expr.assignee),
+            Node::Enum(expr) => {
+                self.declare(&expr.name, false)?;
+                self.scopes.last_mut().unwrap().enums.insert(
+                    expr.name.value.clone(),
+                    expr.values.iter().map(|x| x.name.value.clone()).collect(),
+                );
+                Ok(())
+            }
+            Node::Struct(expr) => self.declare(&expr.name, false),
+            Node::Import(nodes::Import { exposing, .. })
+            | Node::Export(nodes::Export {
+                export: ExportType::From(exposing, _),
+                ..
+            }) => {
+                if exposing.iter().any(|x| matches!(x, ExposeType::Star())) {
+                    self.scopes.last_mut().unwrap().is_dynamic = true;
+                }
+
+                for symbol in Resolver::exposed(exposing, node.location()) {
+                    self.declare(&symbol, false)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The variables an import or export from defines, other than the ones from *
+    fn exposed(exposing: &[ExposeType], location: &Location) -> Vec<nodes::Symbol> {
+        exposing
+            .iter()
+            .filter_map(|expose| {
+                let (name, location) = match expose {
+                    ExposeType::Identifier(symbol) => (&symbol.value, &symbol.location),
+                    ExposeType::IdentifierAs(symbol, name) => (name, &symbol.location),
+                    ExposeType::StarAs(name) => (name, location),
+                    ExposeType::Star() => return None,
+                };
+
+                Some(nodes::Symbol {
+                    value: name.clone(),
+                    address: None,
+                    location: location.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn declare_pattern(&mut self, pattern: &DeclareType) -> Result<(), ZephyrError> {
+        match pattern {
+            DeclareType::Symbol(symbol) => self.declare(symbol, false),
+            DeclareType::Array(items) => items.iter().try_for_each(|x| self.declare_pattern(x)),
+            DeclareType::Object(parts) => parts
+                .iter()
+                .try_for_each(|x| self.declare_pattern(&x.assignee)),
+        }
+    }
+
+    /// Defines the variables of a declaration pattern in order, resolving the defaults along the way
+    fn define_pattern(&mut self, pattern: &mut DeclareType) -> Result<(), ZephyrError> {
+        match pattern {
+            DeclareType::Symbol(symbol) => {
+                self.define(symbol);
+                Ok(())
+            }
+            DeclareType::Array(items) => items.iter_mut().try_for_each(|x| self.define_pattern(x)),
+            DeclareType::Object(parts) => {
+                for part in parts {
+                    if let Some(default) = &mut part.default {
+                        self.node(default)?;
+                    }
+                    self.define_pattern(&mut part.assignee)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn node(&mut self, node: &mut Node) -> Result<(), ZephyrError> {
+        match node {
+            Node::Symbol(symbol) => self.reference(symbol)?,
+
+            Node::Block(expr) => {
+                self.push();
+                let result = self.statements(&mut expr.nodes);
+                self.pop();
+                result?
+            }
+            Node::ExportedBlock(expr) => self.statements(&mut expr.nodes)?,
+
+            Node::Declare(expr) => self.declare_value(expr)?,
+            Node::Export(expr) => match &mut expr.export {
+                ExportType::Declaration(declare) => self.declare_value(declare)?,
+                ExportType::From(exposing, _) => {
+                    for symbol in Resolver::exposed(exposing, &expr.location) {
+                        self.define(&symbol);
+                    }
+                }
+                ExportType::Symbol(_) | ExportType::Object(_) => (),
+            },
+            Node::Import(expr) => {
+                for symbol in Resolver::exposed(&expr.exposing, &expr.location) {
+                    self.define(&symbol);
+                }
+            }
+            Node::Assign(expr) => {
+                self.node(&mut expr.value)?;
+                self.node(&mut expr.assignee)?;
+            }
+            Node::Enum(expr) => {
+                for method in &mut expr.methods {
+                    self.function(method)?;
+                }
+                self.define(&expr.name);
+            }
+            Node::Struct(expr) => {
+                if let Some(extends) = &mut expr.extends {
+                    self.node(extends)?;
+                }
+                for method in &mut expr.methods {
+                    self.function(method)?;
+                }
+                self.define(&expr.name);
+            }
+            Node::Function(expr) => self.function(expr)?,
+
+            Node::If(expr) => {
+                self.node(&mut expr.test)?;
+                self.node(&mut expr.succss)?;
+                if let Some(alternate) = &mut expr.alternate {
+                    self.node(alternate)?;
+                }
+            }
+            Node::Match(expr) => self.match_expr(expr)?,
+            Node::WhileLoop(expr) => {
+                self.node(&mut expr.test)?;
+                self.node(&mut expr.body)?;
+                if let Some(on_break) = &mut expr.on_break {
+                    self.node(on_break)?;
+                }
+            }
+            Node::For(expr) => {
+                self.node(&mut expr.iterator)?;
+
+                // Each iteration has a scope with the index and value
+                self.push();
+                let result = self.for_body(expr);
+                self.pop();
+                result?;
+
+                if let Some(on_break) = &mut expr.on_break {
+                    self.node(on_break)?;
+                }
+            }
+            Node::Try(expr) => {
+                let tail_calls = std::mem::replace(&mut self.tail_calls, false);
+                let result = self.try_expr(expr);
+                self.tail_calls = tail_calls;
+                result?
+            }
+
+            Node::Interrupt(expr) => {
+                if let InterruptType::Return(Some(value)) = &mut expr.t {
+                    expr.tail_call = self.tail_calls && matches!(**value, Node::Call(_));
+                    self.node(value)?;
+                }
+            }
+            Node::Yield(expr) => {
+                if let Some(value) = &mut expr.value {
+                    self.node(value)?;
+                }
+            }
+            Node::Throw(expr) => self.node(&mut expr.value)?,
+            Node::Await(expr) => self.node(&mut expr.value)?,
+            Node::Debug(expr) => self.node(&mut expr.node)?,
+
+            Node::Arithmetic(expr) => {
+                self.node(&mut expr.left)?;
+                self.node(&mut expr.right)?;
+            }
+            Node::Comp(expr) => {
+                self.node(&mut expr.left)?;
+                self.node(&mut expr.right)?;
+            }
+            Node::Coalesce(expr) => {
+                self.node(&mut expr.left)?;
+                self.node(&mut expr.right)?;
+            }
+            Node::Logical(expr) => {
+                self.node(&mut expr.left)?;
+                self.node(&mut expr.right)?;
+            }
+            Node::Is(expr) => {
+                self.node(&mut expr.left)?;
+                self.is_type(&mut expr.right)?;
+            }
+            Node::Range(expr) => {
+                self.node(&mut expr.start)?;
+                self.node(&mut expr.end)?;
+                if let Some(step) = &mut expr.step {
+                    self.node(step)?;
+                }
+            }
+            Node::Unary(expr) => self.node(&mut expr.value)?,
+            Node::Spread(expr) => self.node(&mut expr.value)?,
+
+            Node::Call(expr) => {
+                self.node(&mut expr.left)?;
+                for arg in &mut expr.args {
+                    self.node(arg)?;
+                }
+                for (_, arg) in &mut expr.named_args {
+                    self.node(arg)?;
+                }
+            }
+            Node::Member(expr) => {
+                self.node(&mut expr.left)?;
+                // a.b is a key, not a variable
+                if expr.computed {
+                    self.node(&mut expr.right)?;
+                }
+            }
+
+            Node::Array(expr) => {
+                for item in &mut expr.items {
+                    self.node(item)?;
+                }
+            }
+            Node::Object(expr) => {
+                for item in &mut expr.items {
+                    match item {
+                        ObjectItem::Property(_, value) => self.node(&mut value.value)?,
+                        ObjectItem::Spread(spread) => self.node(&mut spread.value)?,
+                    }
+                }
+            }
+            Node::TemplateString(expr) => {
+                for part in &mut expr.parts {
+                    self.node(part)?;
+                }
+            }
+            Node::Number(_) | Node::ZString(_) => (),
+        }
+
+        Ok(())
+    }
+
+    fn declare_value(&mut self, expr: &mut nodes::Declare) -> Result<(), ZephyrError> {
+        if let Some(value) = &mut expr.value {
+            self.node(value)?;
+        }
+        self.define_pattern(&mut expr.assignee)
+    }
+
+    fn for_body(&mut self, expr: &mut nodes::For) -> Result<(), ZephyrError> {
+        self.declare(&expr.index_symbol, true)?;
+        if let Some(symbol) = &expr.value_symbol {
+            self.declare(symbol, true)?;
+        }
+        self.node(&mut expr.block)
+    }
+
+    /// Functions are called in a scope with their arguments, which the body's block is inside of
+    fn function(&mut self, expr: &mut nodes::Function) -> Result<(), ZephyrError> {
+        self.scopes.push(ResolverScope {
+            is_function: true,
+            ..Default::default()
+        });
+        let tail_calls = std::mem::replace(&mut self.tail_calls, !expr.is_generator);
+        let result = self.function_inner(expr);
+        self.tail_calls = tail_calls;
+        self.pop();
+        result
+    }
+
+    fn function_inner(&mut self, expr: &mut nodes::Function) -> Result<(), ZephyrError> {
+        // Defaults are ran in the arguments' scope after the previous arguments are defined
+        let args = &mut expr.args;
+        for argument in &mut args.positional {
+            self.argument(&argument.name, &mut argument.default)?;
+        }
+        if let Some(rest) = &args.positional_rest {
+            self.declare(rest, true)?;
+        }
+        for argument in &mut args.named {
+            self.argument(&argument.name, &mut argument.default)?;
+        }
+        if let Some(rest) = &args.named_rest {
+            self.declare(rest, true)?;
+        }
+
+        // Generators run their body's statements straight in the arguments' scope
+        if expr.is_generator {
+            self.statements(&mut expr.body.nodes)
+        } else {
+            self.push();
+            let result = self.statements(&mut expr.body.nodes);
+            self.pop();
+            result
+        }
+    }
+
+    fn argument(
+        &mut self,
+        name: &nodes::Symbol,
+        default: &mut Option<Box<Node>>,
+    ) -> Result<(), ZephyrError> {
+        if let Some(default) = default {
+            self.node(default)?;
+        }
+        self.declare(name, true)
+    }
+
+    fn try_expr(&mut self, expr: &mut nodes::Try) -> Result<(), ZephyrError> {
+        self.node(&mut expr.attempt)?;
+        match &mut expr.catch {
+            Some(CatchType::Value(value)) => self.node(value)?,
+            Some(CatchType::Binding(symbol, block)) => {
+                self.push();
+                let result = self.declare(symbol, true).and_then(|_| self.node(block));
+                self.pop();
+                result?
+            }
+            Some(CatchType::Return) | None => (),
+        }
+        if let Some(finally) = &mut expr.finally {
+            self.node(finally)?;
+        }
+        Ok(())
+    }
+
+    fn is_type(&mut self, is: &mut IsType) -> Result<(), ZephyrError> {
+        match is {
+            IsType::Basic(node) | IsType::Comparison(_, node) => self.node(node),
+        }
+    }
+
+    fn match_expr(&mut self, expr: &mut nodes::Match) -> Result<(), ZephyrError> {
+        self.check_enum_match(expr);
+        self.node(&mut expr.test)?;
+
+        for case in &mut expr.cases {
+            match case {
+                MatchCaseType::MatchCase(case) => {
+                    // Values in the pattern are ran before its bindings exist
+                    self.pattern_values(&mut case.pattern)?;
+
+                    let mut bindings: Vec<nodes::Symbol> = vec![];
+                    Resolver::pattern_bindings(&case.pattern, &mut bindings);
+
+                    self.push();
+                    let result = self.match_case(case, &bindings);
+                    self.pop();
+                    result?;
+                }
+                MatchCaseType::Else(value) => self.node(value)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warns when a match without an else names variants of an enum, but not all of them
+    ///
+    /// Only enums declared in the file are known, as imported ones only exist once it runs
+    fn check_enum_match(&mut self, expr: &nodes::Match) {
+        if expr
+            .cases
+            .iter()
+            .any(|x| matches!(x, MatchCaseType::Else(_)))
+        {
+            return;
+        }
+
+        // Guarded cases may not match, so they do not handle their variants
+        let mut named: Vec<(String, String)> = vec![];
+        let mut covered: HashSet<(String, String)> = HashSet::new();
+        for case in &expr.cases {
+            let MatchCaseType::MatchCase(case) = case else {
+                continue;
+            };
+
+            let mut variants = vec![];
+            Resolver::pattern_variants(&case.pattern, &mut variants);
+            if case.guard.is_none() {
+                covered.extend(variants.iter().cloned());
+            }
+            named.extend(variants);
+        }
+
+        let mut enums: Vec<String> = vec![];
+        for (enum_name, _) in named {
+            if !enums.contains(&enum_name) {
+                enums.push(enum_name);
+            }
+        }
+
+        let mut missing: Vec<String> = vec![];
+        for enum_name in enums {
+            let Some(variants) = self.enum_variants(&enum_name) else {
+                continue;
+            };
+
+            missing.extend(
+                variants
+                    .iter()
+                    .filter(|x| !covered.contains(&(enum_name.clone(), x.to_string())))
+                    .map(|x| format!("{}.{}", enum_name, x)),
+            );
+        }
+
+        if !missing.is_empty() {
+            self.warnings.push(ZephyrError {
+                code: ErrorCode::NonExhaustiveMatch,
+                message: format!("match does not handle {}", missing.join(", ")),
+                location: Some(expr.location.clone()),
+            });
+        }
+    }
+
+    /// Collects the enum and variant of each `X.A` the pattern names
+    fn pattern_variants(pattern: &MatchPattern, variants: &mut Vec<(String, String)>) {
+        let node = match pattern {
+            MatchPattern::Is(IsType::Basic(node), _)
+            | MatchPattern::Comparison(Comparison::Eq, node) => node,
+            MatchPattern::Alternatives(patterns) => {
+                for pattern in patterns {
+                    Resolver::pattern_variants(pattern, variants);
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        if let Node::Member(nodes::Member {
+            left,
+            right,
+            computed: false,
+            ..
+        }) = &**node
+        {
+            if let (Node::Symbol(left), Node::Symbol(right)) = (&**left, &**right) {
+                variants.push((left.value.clone(), right.value.clone()));
+            }
+        }
+    }
+
+    /// The variants of the enum the name refers to, None if it is not an enum declared here
+    fn enum_variants(&self, name: &str) -> Option<&Vec<String>> {
+        for scope in self.scopes.iter().rev() {
+            if scope.is_dynamic {
+                return None;
+            }
+            if scope.slots.contains_key(name) {
+                return scope.enums.get(name);
+            }
+        }
+
+        None
+    }
+
+    /// The guard and the case share the scope with the bindings
+    fn match_case(
+        &mut self,
+        case: &mut nodes::MatchCase,
+        bindings: &[nodes::Symbol],
+    ) -> Result<(), ZephyrError> {
+        for binding in bindings {
+            self.declare(binding, true)?;
+        }
+        if let Some(guard) = &mut case.guard {
+            self.node(guard)?;
+        }
+        self.node(&mut case.success)
+    }
+
+    fn pattern_values(&mut self, pattern: &mut MatchPattern) -> Result<(), ZephyrError> {
+        match pattern {
+            MatchPattern::Comparison(_, node) => self.node(node),
+            MatchPattern::Is(is, _) => self.is_type(is),
+            MatchPattern::Binding(_) => Ok(()),
+            MatchPattern::Array(patterns, _) | MatchPattern::Alternatives(patterns) => {
+                patterns.iter_mut().try_for_each(|x| self.pattern_values(x))
+            }
+            MatchPattern::Object(parts) => parts
+                .iter_mut()
+                .try_for_each(|(_, x)| self.pattern_values(x)),
+        }
+    }
+
+    /// Collects what a pattern binds, in the order Interpreter::match_pattern binds them
+    fn pattern_bindings(pattern: &MatchPattern, bindings: &mut Vec<nodes::Symbol>) {
+        match pattern {
+            MatchPattern::Comparison(..) => (),
+            MatchPattern::Is(_, binding) => bindings.extend(binding.clone()),
+            MatchPattern::Binding(symbol) => bindings.push(symbol.clone()),
+            MatchPattern::Array(patterns, rest) => {
+                for pattern in patterns {
+                    Resolver::pattern_bindings(pattern, bindings);
+                }
+                bindings.extend(rest.clone());
+            }
+            MatchPattern::Object(parts) => {
+                for (_, pattern) in parts {
+                    Resolver::pattern_bindings(pattern, bindings);
+                }
+            }
+            MatchPattern::Alternatives(patterns) => {
+                // Only one alternative binds, so the same name in several is not a duplicate
+                let start = bindings.len();
+                for pattern in patterns {
+                    let mut inner: Vec<nodes::Symbol> = vec![];
+                    Resolver::pattern_bindings(pattern, &mut inner);
+                    for symbol in inner {
+                        if !bindings[start..].iter().any(|x| x.value == symbol.value) {
+                            bindings.push(symbol);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        errors::{ErrorCode, ZephyrError},
+        lexer::lexer::lex,
+        parser::{
+            nodes::{self, Address, CatchType, InterruptType, MatchCaseType, Node},
+            Parser,
+        },
+    };
+
+    use super::Resolver;
+
+    fn resolve(code: &str) -> Result<Node, ZephyrError> {
+        let mut node = Parser::new(lex(code, String::new()).unwrap(), String::new())
+            .produce_ast()
+            .unwrap();
+        Resolver::new(vec![]).resolve(&mut node)?;
+        Ok(node)
+    }
+
+    fn statements(node: &Node) -> &Vec<Node> {
+        match node {
+            Node::Block(block) => &block.nodes,
+            x => panic!("Expected a block, got {:?}", x),
+        }
+    }
+
+    fn address(node: &Node) -> Option<Address> {
+        match node {
+            Node::Symbol(symbol) => symbol.address,
+            x => panic!("Expected a symbol, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn hoisted_functions() {
+        let node = resolve("func a() { b } func b() { 1 }").unwrap();
+
+        let Node::Declare(ref declare) = statements(&node)[0] else {
+            panic!("Expected a declaration");
+        };
+        let Some(Node::Function(ref function)) = declare.value.as_deref() else {
+            panic!("Expected a function");
+        };
+
+        // The body, the arguments, then the block b is declared in
+        assert_eq!(
+            address(&function.body.nodes[0]),
+            Some(Address { depth: 2, slot: 1 })
+        );
+    }
+
+    #[test]
+    fn used_before_declared() {
+        let err = resolve("b; let b = 1;").unwrap_err();
+        assert!(matches!(err.code, ErrorCode::UnknownReference));
+    }
+
+    #[test]
+    fn shadowed_in_block() {
+        let node = resolve("let x = 1; { x; let x = 2; x; }").unwrap();
+        let inner = statements(&statements(&node)[1]);
+
+        assert_eq!(
+            address(&inner[0]),
+            Some(Address { depth: 1, slot: 0 }),
+            "Expected x to be the outer x before the inner one is declared"
+        );
+        assert_eq!(address(&inner[2]), Some(Address { depth: 0, slot: 0 }));
+    }
+
+    #[test]
+    fn match_bindings() {
+        let node = resolve("match [1] { [a] -> a }").unwrap();
+
+        let Node::Match(ref expr) = statements(&node)[0] else {
+            panic!("Expected a match");
+        };
+        let MatchCaseType::MatchCase(ref case) = expr.cases[0] else {
+            panic!("Expected a case");
+        };
+        // Cases are blocks inside of the scope with the bindings
+        assert_eq!(
+            address(&statements(&case.success)[0]),
+            Some(Address { depth: 1, slot: 0 })
+        );
+    }
+
+    #[test]
+    fn catch_bindings() {
+        let node = resolve("try { throw 1 } catch e { e }").unwrap();
+
+        let Node::Try(ref expr) = statements(&node)[0] else {
+            panic!("Expected a try");
+        };
+        let Some(CatchType::Binding(_, ref block)) = expr.catch else {
+            panic!("Expected a catch binding");
+        };
+
+        // The block is inside of the scope with e
+        assert_eq!(
+            address(&statements(block)[0]),
+            Some(Address { depth: 1, slot: 0 })
+        );
+
+        let node = resolve("try { throw 1 } catch e return e").unwrap();
+
+        let Node::Try(ref expr) = statements(&node)[0] else {
+            panic!("Expected a try");
+        };
+        let Some(CatchType::Binding(_, ref value)) = expr.catch else {
+            panic!("Expected catch e return to bind e");
+        };
+        let Node::Interrupt(nodes::Interrupt {
+            t: InterruptType::Return(Some(ref value)),
+            ..
+        }) = **value
+        else {
+            panic!("Expected catch e return to return");
+        };
+
+        assert_eq!(address(value), Some(Address { depth: 0, slot: 0 }));
+    }
+
+    #[test]
+    fn enum_match_coverage() {
+        let warnings = |code: &str| {
+            let mut node = Parser::new(lex(code, String::new()).unwrap(), String::new())
+                .produce_ast()
+                .unwrap();
+            let mut resolver = Resolver::new(vec![]);
+            resolver.resolve(&mut node).unwrap();
+            resolver
+                .warnings
+                .into_iter()
+                .map(|x| x.message)
+                .collect::<Vec<String>>()
+        };
+
+        // The same variant name from another enum does not handle it
+        assert_eq!(
+            warnings("enum A { X, Y } enum B { X } match 1 { A.Y -> 1, is B.X -> 2 }"),
+            vec!["match does not handle A.X"]
+        );
+        // Guarded cases do not count, but an else handles everything
+        assert_eq!(
+            warnings("enum A { X, Y } match 1 { A.X -> 1, A.Y where 1 == 2 -> 2 }"),
+            vec!["match does not handle A.Y"]
+        );
+        assert!(warnings("enum A { X, Y } match 1 { A.X -> 1, else -> 2 }").is_empty());
+        assert!(warnings("enum A { X, Y } func f(v) { match v { A.X | A.Y -> 1 } }").is_empty());
+        // A variable which is not an enum is not checked
+        assert!(warnings("let A = .{ X: 1 }; match 1 { A.X -> 1 }").is_empty());
+    }
+
+    #[test]
+    fn expose_star() {
+        let node = resolve(r#"import "./a.zr" expose *; x;"#).unwrap();
+
+        assert_eq!(
+            address(&statements(&node)[1]),
+            None,
+            "Expected x to be looked up by name, as it may come from the import"
+        );
+    }
+
+    #[test]
+    fn duplicate_declarations() {
+        let err = resolve("let a = 1; let a = 2;").unwrap_err();
+        assert!(matches!(err.code, ErrorCode::AlreadyDefined));
+
+        // Inner scopes can declare the same names
+        assert!(resolve("let a = 1; { let a = 2; }").is_ok());
+    }
+}
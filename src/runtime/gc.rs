@@ -0,0 +1,401 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+use super::{
+    scope::{Scope, ScopeInnerType},
+    values::{ObjectItemsType, RuntimeValue},
+};
+
+type ArrayItemsType = Rc<RefCell<Vec<RuntimeValue>>>;
+
+/// The fewest values which have to be tracked since the last collection before collecting again
+const MIN_COLLECT_THRESHOLD: usize = 10_000;
+
+/// Everything which can be part of a cycle, objects and arrays can hold themselves, and scopes can
+/// hold the functions which close over them
+///
+/// Values are still freed by their Rc, this only finds the ones which are only kept alive by each
+/// other and empties them so the Rcs drop
+struct Heap {
+    scopes: Vec<Weak<RefCell<Scope>>>,
+    /// The scopes already being tracked, as the same scope is closed over by many functions
+    tracked_scopes: HashSet<*const RefCell<Scope>>,
+    objects: Vec<Weak<RefCell<HashMap<String, RuntimeValue>>>>,
+    arrays: Vec<Weak<RefCell<Vec<RuntimeValue>>>>,
+    /// How many values were tracked since the last collection
+    allocated: usize,
+    threshold: usize,
+    collections: usize,
+    freed: usize,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        scopes: vec![],
+        tracked_scopes: HashSet::new(),
+        objects: vec![],
+        arrays: vec![],
+        allocated: 0,
+        threshold: MIN_COLLECT_THRESHOLD,
+        collections: 0,
+        freed: 0,
+    });
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeapStats {
+    pub scopes: usize,
+    pub objects: usize,
+    pub arrays: usize,
+    pub collections: usize,
+    /// How many values were freed by all of the collections
+    pub freed: usize,
+}
+
+pub fn track_object(items: &ObjectItemsType) {
+    track(|heap| heap.objects.push(Rc::downgrade(items)));
+}
+
+pub fn track_array(items: &ArrayItemsType) {
+    track(|heap| heap.arrays.push(Rc::downgrade(items)));
+}
+
+/// Tracks a scope which a function closes over, along with its parents
+///
+/// Other scopes are not tracked, a scope can only be part of a cycle through a function which
+/// closes over it or one of its children
+pub fn track_scope(scope: &ScopeInnerType) {
+    let mut scope = Some(scope.clone());
+    while let Some(current) = scope {
+        let tracked = HEAP.with_borrow(|heap| heap.tracked_scopes.contains(&Rc::as_ptr(&current)));
+        if tracked {
+            break;
+        }
+
+        track(|heap| {
+            heap.tracked_scopes.insert(Rc::as_ptr(&current));
+            heap.scopes.push(Rc::downgrade(&current));
+        });
+
+        scope = current.try_borrow().ok().and_then(|x| x.parent.clone());
+    }
+}
+
+/// Adds a value to the heap, collecting it if enough has been tracked since the last time
+///
+/// This is done on every allocation rather than on calls, so loops which only allocate still
+/// free their cycles, and the Weaks of values which were already dropped are cleared out
+fn track(add: impl FnOnce(&mut Heap)) {
+    let due = HEAP.with_borrow_mut(|heap| {
+        add(heap);
+        heap.allocated += 1;
+        heap.allocated >= heap.threshold
+    });
+
+    if due {
+        collect();
+    }
+}
+
+pub fn stats() -> HeapStats {
+    HEAP.with_borrow(|heap| HeapStats {
+        scopes: heap.scopes.iter().filter(|x| x.strong_count() > 0).count(),
+        objects: heap.objects.iter().filter(|x| x.strong_count() > 0).count(),
+        arrays: heap.arrays.iter().filter(|x| x.strong_count() > 0).count(),
+        collections: heap.collections,
+        freed: heap.freed,
+    })
+}
+
+enum HeapNode {
+    Scope(ScopeInnerType),
+    Object(ObjectItemsType),
+    Array(ArrayItemsType),
+}
+
+impl HeapNode {
+    fn id(&self) -> usize {
+        match self {
+            HeapNode::Scope(x) => Rc::as_ptr(x) as usize,
+            HeapNode::Object(x) => Rc::as_ptr(x) as usize,
+            HeapNode::Array(x) => Rc::as_ptr(x) as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            HeapNode::Scope(x) => Rc::strong_count(x),
+            HeapNode::Object(x) => Rc::strong_count(x),
+            HeapNode::Array(x) => Rc::strong_count(x),
+        }
+    }
+
+    /// Gives the id of everything the node holds a Rc of, false if it is borrowed so it could not
+    /// be looked at
+    fn edges(&self, f: &mut impl FnMut(usize)) -> bool {
+        match self {
+            HeapNode::Scope(x) => {
+                let Ok(scope) = x.try_borrow() else {
+                    return false;
+                };
+                if let Some(parent) = &scope.parent {
+                    f(Rc::as_ptr(parent) as usize);
+                }
+                for (_, variable) in &scope.slots {
+                    value_edges(&variable.value, f);
+                }
+            }
+            HeapNode::Object(x) => {
+                let Ok(items) = x.try_borrow() else {
+                    return false;
+                };
+                for value in items.values() {
+                    value_edges(value, f);
+                }
+            }
+            HeapNode::Array(x) => {
+                let Ok(items) = x.try_borrow() else {
+                    return false;
+                };
+                for value in items.iter() {
+                    value_edges(value, f);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Empties the node, dropping the Rcs it holds so the cycle it is part of is broken
+    /// Anything borrowed is in use, so it is left alone
+    fn clear(&self) {
+        match self {
+            HeapNode::Scope(x) => {
+                let Ok(mut scope) = x.try_borrow_mut() else {
+                    return;
+                };
+                let slots = std::mem::take(&mut scope.slots);
+                let parent = scope.parent.take();
+                scope.names.clear();
+                drop(scope);
+                drop((slots, parent));
+            }
+            HeapNode::Object(x) => {
+                let Ok(mut items) = x.try_borrow_mut() else {
+                    return;
+                };
+                let items = std::mem::take(&mut *items);
+                drop(items);
+            }
+            HeapNode::Array(x) => {
+                let Ok(mut items) = x.try_borrow_mut() else {
+                    return;
+                };
+                let items = std::mem::take(&mut *items);
+                drop(items);
+            }
+        }
+    }
+}
+
+/// Gives the id of everything the value holds a Rc of which could be tracked
+fn value_edges(value: &RuntimeValue, f: &mut impl FnMut(usize)) {
+    match value {
+        RuntimeValue::Object(x) => f(Rc::as_ptr(&x.items) as usize),
+        RuntimeValue::Array(x) => f(Rc::as_ptr(&x.items) as usize),
+        RuntimeValue::Function(x) => f(Rc::as_ptr(&x.inner.scope) as usize),
+        RuntimeValue::Export(x) => f(Rc::as_ptr(&x.scope) as usize),
+        RuntimeValue::EnumVariant(x) => value_edges(&x.inner, f),
+        _ => (),
+    }
+
+    // Methods got via a value hold it to give as self
+    if let Some(bound) = &value.options().proto_value {
+        value_edges(bound, f);
+    }
+}
+
+/// Frees every tracked value which is only kept alive by other tracked values, giving how many
+///
+/// A value is in use if it has more Rcs than the tracked values hold, as something else, like a
+/// variable in a scope which is not tracked or a value the interpreter is working with, holds it.
+/// Everything reachable from those is in use too, and the rest can only reach each other.
+/// Anything borrowed while collecting is treated as in use, so this can run at any point
+pub fn collect() -> usize {
+    let nodes = HEAP.with_borrow_mut(|heap| {
+        heap.scopes.retain(|x| x.strong_count() > 0);
+        heap.objects.retain(|x| x.strong_count() > 0);
+        heap.arrays.retain(|x| x.strong_count() > 0);
+        heap.tracked_scopes = heap.scopes.iter().map(|x| x.as_ptr()).collect();
+
+        heap.scopes
+            .iter()
+            .filter_map(|x| x.upgrade().map(HeapNode::Scope))
+            .chain(
+                heap.objects
+                    .iter()
+                    .filter_map(|x| x.upgrade().map(HeapNode::Object)),
+            )
+            .chain(
+                heap.arrays
+                    .iter()
+                    .filter_map(|x| x.upgrade().map(HeapNode::Array)),
+            )
+            .collect::<Vec<HeapNode>>()
+    });
+
+    let index = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.id(), i))
+        .collect::<HashMap<usize, usize>>();
+
+    // How many of each node's Rcs are held by other nodes
+    let mut internal = vec![0; nodes.len()];
+    let mut readable = vec![false; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        readable[i] = node.edges(&mut |id| {
+            if let Some(&j) = index.get(&id) {
+                internal[j] += 1;
+            }
+        });
+    }
+
+    // The Rc in nodes is not counted
+    let mut live = (0..nodes.len())
+        .map(|i| !readable[i] || nodes[i].strong_count() - 1 != internal[i])
+        .collect::<Vec<bool>>();
+    let mut stack = (0..nodes.len())
+        .filter(|i| live[*i])
+        .collect::<Vec<usize>>();
+    while let Some(i) = stack.pop() {
+        nodes[i].edges(&mut |id| {
+            if let Some(&j) = index.get(&id) {
+                if !live[j] {
+                    live[j] = true;
+                    stack.push(j);
+                }
+            }
+        });
+    }
+
+    let garbage = nodes
+        .iter()
+        .zip(&live)
+        .filter(|(_, live)| !**live)
+        .map(|(node, _)| node)
+        .collect::<Vec<&HeapNode>>();
+    for node in &garbage {
+        node.clear();
+    }
+
+    let freed = garbage.len();
+    let remaining = nodes.len() - freed;
+    drop(garbage);
+    drop(nodes);
+
+    HEAP.with_borrow_mut(|heap| {
+        heap.allocated = 0;
+        heap.threshold = remaining.max(MIN_COLLECT_THRESHOLD);
+        heap.collections += 1;
+        heap.freed += freed;
+    });
+
+    freed
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        lexer::tokens::NO_LOCATION,
+        parser::nodes,
+        runtime::{
+            scope::{Scope, Variable},
+            values::{self, RuntimeValueDetails, RuntimeValueUtils},
+        },
+    };
+
+    use super::{collect, stats, track_scope};
+
+    #[test]
+    fn self_cycle() {
+        let obj = values::Object::new_empty();
+        obj.items.borrow_mut().insert("me".to_string(), obj.wrap());
+        let items = Rc::downgrade(&obj.items);
+        drop(obj);
+
+        assert_eq!(
+            items.strong_count(),
+            1,
+            "Expected the object to keep itself alive"
+        );
+        assert_eq!(collect(), 1);
+        assert_eq!(items.strong_count(), 0, "Expected the object to be freed");
+    }
+
+    #[test]
+    fn closure_in_own_scope() {
+        let scope = Rc::new(RefCell::new(Scope::new(String::new())));
+        let func = values::Function {
+            options: RuntimeValueDetails::default(),
+            inner: values::FunctionInner {
+                body: Rc::new(nodes::Block {
+                    nodes: vec![],
+                    location: NO_LOCATION.clone(),
+                }),
+                name: None,
+                arguments: Rc::default(),
+                scope: scope.clone(),
+                is_generator: false,
+                is_async: false,
+                chunk: None,
+            },
+        };
+        scope
+            .borrow_mut()
+            .insert("f", Variable::from(func.wrap()), None)
+            .unwrap();
+        track_scope(&scope);
+
+        let weak = Rc::downgrade(&scope);
+        drop((scope, func));
+
+        assert_eq!(
+            weak.strong_count(),
+            1,
+            "Expected the scope to be kept alive by f"
+        );
+        collect();
+        assert_eq!(weak.strong_count(), 0, "Expected the scope to be freed");
+    }
+
+    #[test]
+    fn live_data_survives() {
+        let inner = values::Object::new_empty();
+        inner
+            .items
+            .borrow_mut()
+            .insert("me".to_string(), inner.wrap());
+        let arr = values::Array::new(vec![inner.wrap()]);
+        drop(inner);
+
+        // Only kept alive through arr, which is held here
+        assert_eq!(collect(), 0);
+        assert_eq!(arr.items.borrow().len(), 1);
+
+        let values::RuntimeValue::Object(inner) = arr.items.borrow()[0].clone() else {
+            panic!("Expected the array to still hold the object");
+        };
+        assert!(
+            inner.items.borrow().contains_key("me"),
+            "Expected the object to keep its items"
+        );
+        assert_eq!(stats().objects, 1);
+        assert_eq!(stats().arrays, 1);
+    }
+}
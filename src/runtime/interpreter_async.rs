@@ -0,0 +1,282 @@
+use std::sync::mpsc::TryRecvError;
+
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    lexer::tokens::{Location, NO_LOCATION},
+    parser::nodes,
+    util::colors,
+};
+
+use super::{
+    values::{self, FunctionType, PromiseState, RuntimeValue},
+    zephyr_mspc::MspcSendType,
+    Interpreter, R,
+};
+
+impl Interpreter {
+    /// Runs the jobs sent from threads until done gives true, or there are no threads left to send any
+    pub fn run_event_loop(&mut self, done: &dyn Fn() -> bool) -> Result<(), ZephyrError> {
+        let Some(receiver) = self.receiver.clone() else {
+            return Ok(());
+        };
+
+        while !done() {
+            match receiver.try_recv() {
+                Ok(value) => match value {
+                    MspcSendType::ThreadCreate => {
+                        self.thread_count.set(self.thread_count.get() + 1)
+                    }
+                    MspcSendType::ThreadDestroy => {
+                        self.thread_count.set(self.thread_count.get() - 1)
+                    }
+                    MspcSendType::ThreadMessage(job) => {
+                        let func = match job.timer {
+                            Some(ref timer) => match self.timer_function(timer) {
+                                Some(func) => func,
+                                // Jobs a timer posted before it was cleared are dropped
+                                None => continue,
+                            },
+                            None => match self.function_ids.borrow().get(&job.func).cloned() {
+                                Some(func) => func,
+                                None => {
+                                    panic!("Function id was not found in the hash map {}", job.func)
+                                }
+                            },
+                        };
+
+                        self.run_function(func.clone(), job.args.into(), NO_LOCATION.clone())?;
+                    }
+                    MspcSendType::PromiseSettle(id, result) => {
+                        let Some(promise) = self.promises.borrow_mut().remove(&id) else {
+                            panic!("Promise id was not found in the hash map {}", id)
+                        };
+
+                        promise.settle(result.map(|x| RuntimeValue::from(&x)).map_err(|message| {
+                            ZephyrError {
+                                message,
+                                code: ErrorCode::RuntimeError,
+                                location: None,
+                            }
+                        }));
+                        self.track_rejection(&promise);
+                    }
+                },
+                Err(TryRecvError::Empty) => {
+                    if self.thread_count.get() == 0 {
+                        break;
+                    }
+                    std::thread::yield_now();
+                }
+                Err(TryRecvError::Disconnected) => {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gives the function a timer's job runs, None if the timer has been cleared
+    /// One-shot timers are forgotten, as they only post the one job
+    pub fn timer_function(&self, id: &str) -> Option<FunctionType> {
+        let timers = self.timers.borrow();
+        let timer = timers.get(id)?;
+        if timer.repeat {
+            return self.function_ids.borrow().get(&timer.func).cloned();
+        }
+
+        drop(timers);
+        self.clear_timer(id)
+    }
+
+    /// Stops a timer and forgets its function, giving the function if the timer was running
+    /// Dropping the timer's sender wakes its thread up, telling it to stop
+    pub fn clear_timer(&self, id: &str) -> Option<FunctionType> {
+        let timer = self.timers.borrow_mut().remove(id)?;
+        self.function_ids.borrow_mut().remove(&timer.func)
+    }
+
+    /// Creates a promise which a thread will settle using its id
+    pub fn make_thread_promise(&self) -> values::Promise {
+        let promise = values::Promise::new();
+        self.promises
+            .borrow_mut()
+            .insert(promise.id, promise.clone());
+        promise
+    }
+
+    /// Remembers the promise if it was rejected, so it can be reported if it is never awaited
+    pub fn track_rejection(&self, promise: &values::Promise) {
+        if promise.is_rejected() {
+            let mut rejected = self.rejected.borrow_mut();
+            rejected.retain(|x| !x.handled.get());
+            rejected.push(promise.clone());
+        }
+    }
+
+    /// Reports the rejected promises which were never awaited, as their errors would be lost
+    pub fn report_rejections(&self) {
+        for promise in self.rejected.take() {
+            if promise.handled.get() {
+                continue;
+            }
+
+            if let PromiseState::Rejected(err) = &*promise.state.borrow() {
+                eprintln!(
+                    "{}Warning: a promise was rejected but never awaited{}",
+                    colors::FG_YELLOW,
+                    colors::COLOR_RESET
+                );
+                eprintln!("{}", err.visualise());
+            }
+        }
+    }
+
+    pub fn run_await(&mut self, expr: nodes::Await) -> R {
+        let value = self.run(*expr.value)?;
+        self.await_value(value, &expr.location)
+    }
+
+    /// Waits for a promise to be settled, running the event loop in the meantime
+    /// Anything other than a promise is given back as-is
+    pub fn await_value(&mut self, value: RuntimeValue, location: &Location) -> R {
+        let promise = match value {
+            RuntimeValue::Promise(promise) => promise,
+            value => return Ok(value),
+        };
+        promise.handled.set(true);
+
+        if promise.is_pending() {
+            let waiting = promise.clone();
+            self.run_event_loop(&|| !waiting.is_pending())?;
+        }
+
+        let state = promise.state.borrow().clone();
+        match state {
+            PromiseState::Resolved(value) => Ok(value),
+            PromiseState::Rejected(err) => Err(ZephyrError {
+                location: err.location.clone().or(Some(location.clone())),
+                ..err
+            }),
+            PromiseState::Pending => Err(ZephyrError {
+                message: "The promise can never be settled, as there is nothing left to settle it"
+                    .to_string(),
+                code: ErrorCode::RuntimeError,
+                location: Some(location.clone()),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use crate::{lexer::lexer::lex, parser::Parser, runtime::test::run};
+
+    const ASYNC: &str = "async func one() { 1 }
+    async func two() { return one() }
+    async func bad() { throw \"bad\" }";
+
+    #[test]
+    fn promises() {
+        let cases = [
+            ("await one()", "1"),
+            ("one()", "Promise<1>"),
+            // The promise given by one() is not wrapped in another
+            ("await two()", "1"),
+            ("await bad()", "error: bad"),
+            ("try await bad() catch e { e }", "bad"),
+            // Anything other than a promise is given back as-is
+            ("await 2", "2"),
+            (
+                "async func three() { await one() + await two() + 1 } await three()",
+                "3",
+            ),
+        ];
+
+        for (code, expected) in cases {
+            for use_vm in [false, true] {
+                assert_eq!(
+                    run(&format!("{} {}", ASYNC, code), use_vm),
+                    expected,
+                    "{}",
+                    code
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn await_outside_async() {
+        for code in [
+            "func f() { await 1 }",
+            "func f() { let g = async func { 1 }; await g() }",
+        ] {
+            let result =
+                Parser::new(lex(code, String::new()).unwrap(), String::new()).produce_ast();
+            assert_eq!(
+                result.err().map(|x| x.message),
+                Some(
+                    "await can only be used at the top level or inside of an async func"
+                        .to_string()
+                ),
+                "{}",
+                code
+            );
+        }
+
+        assert!(Parser::new(
+            lex("async func f() { await 1 } await f()", String::new()).unwrap(),
+            String::new()
+        )
+        .produce_ast()
+        .is_ok());
+    }
+
+    #[test]
+    fn timers() {
+        // The object is given before the event loop runs, so it shows what the timers did to it
+        let cases = [
+            ("set_timeout(func { s.n += 1 }, 1)", "{n: 1}"),
+            (
+                "const id = set_interval(func { s.n += 1; if s.n == 3 { clear_timer(id) } }, 1)",
+                "{n: 3}",
+            ),
+            (
+                "const t = set_timeout(func { s.n += 1 }, 5); clear_timer(t)",
+                "{n: 0}",
+            ),
+            (
+                "const id = set_interval(func { s.n += 1 }, 1); clear_timer(id); sleep(10)",
+                "{n: 0}",
+            ),
+        ];
+
+        for (code, expected) in cases {
+            for use_vm in [false, true] {
+                assert_eq!(
+                    run(&format!("const s = .{{ n: 0 }}; {}; s", code), use_vm),
+                    expected,
+                    "{}",
+                    code
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn event_loop_exits_once_timers_are_cleared() {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            tx.send(run(
+                "const id = set_interval(func {}, 1)
+                set_timeout(func { clear_timer(id) }, 20)
+                1",
+                false,
+            ))
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)).as_deref(), Ok("1"));
+    }
+}
@@ -1,7 +1,13 @@
-use crate::parser::nodes::{self, MatchCaseType};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    errors::ZephyrError,
+    parser::nodes::{self, MatchCaseType, MatchPattern},
+};
 
 use super::{
-    values::{self, RuntimeValueUtils},
+    scope::{Scope, Variable},
+    values::{self, RuntimeValue, RuntimeValueUtils},
     Interpreter, R,
 };
 
@@ -23,23 +29,126 @@ impl Interpreter {
 
         for test in expr.cases {
             match test {
-                MatchCaseType::MatchCase(l) => {
-                    if value.compare_with(self.run(*l.value)?, l.op, Some(expr.location.clone()))? {
-                        return self.run(*l.success);
+                MatchCaseType::MatchCase(case) => {
+                    let mut bindings: Vec<(nodes::Symbol, RuntimeValue)> = vec![];
+                    if !self.match_pattern(&case.pattern, &value, &mut bindings)? {
+                        continue;
+                    }
+
+                    let mut scope = Scope::new_from_parent(self.scope.clone());
+                    for (symbol, value) in bindings {
+                        scope.insert(symbol.value, Variable::from(value), Some(symbol.location))?;
+                    }
+
+                    // The guard and the case share the scope with the bindings
+                    let old_scope = self.swap_scope(Rc::from(RefCell::from(scope)));
+                    let result = match case.guard {
+                        Some(guard) => match self.run(*guard) {
+                            Ok(passed) if !passed.is_truthy() => None,
+                            Ok(_) => Some(self.run(*case.success)),
+                            Err(err) => Some(Err(err)),
+                        },
+                        None => Some(self.run(*case.success)),
+                    };
+                    self.swap_scope(old_scope);
+
+                    if let Some(result) = result {
+                        return result;
                     }
                 }
                 MatchCaseType::Else(r) => {
                     return self.run(*r);
                 }
-                MatchCaseType::Is(val, is, success) => {
-                    let left = self.run(*val)?;
-                    if self.run_inner_is(left, is)?.is_truthy() {
-                        return self.run(*success);
-                    }
-                }
             }
         }
 
         Ok(values::Null::new().wrap())
     }
+
+    /// Checks if a value fits a pattern, adding anything the pattern binds to bindings
+    pub fn match_pattern(
+        &mut self,
+        pattern: &MatchPattern,
+        value: &RuntimeValue,
+        bindings: &mut Vec<(nodes::Symbol, RuntimeValue)>,
+    ) -> Result<bool, ZephyrError> {
+        Ok(match pattern {
+            MatchPattern::Comparison(op, node) => {
+                let right = self.run(*node.clone())?;
+                self.compare(value.clone(), right, op.clone(), node.location())?
+            }
+            MatchPattern::Is(is, binding) => {
+                let matched = self.run_inner_is(value.clone(), is.clone())?.is_truthy();
+
+                if let (true, Some(binding)) = (matched, binding) {
+                    let inner = match value {
+                        RuntimeValue::EnumVariant(variant) => *variant.inner.clone(),
+                        _ => value.clone(),
+                    };
+                    bindings.push((binding.clone(), inner));
+                }
+
+                matched
+            }
+            MatchPattern::Binding(symbol) => {
+                bindings.push((symbol.clone(), value.clone()));
+                true
+            }
+            MatchPattern::Array(patterns, rest) => {
+                let RuntimeValue::Array(arr) = value else {
+                    return Ok(false);
+                };
+                let items = arr.items.borrow().clone();
+
+                if items.len() < patterns.len() || (rest.is_none() && items.len() != patterns.len())
+                {
+                    return Ok(false);
+                }
+
+                for (pattern, item) in patterns.iter().zip(items.iter()) {
+                    if !self.match_pattern(pattern, item, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                if let Some(rest) = rest {
+                    bindings.push((
+                        rest.clone(),
+                        values::Array::new(items[patterns.len()..].to_vec()).wrap(),
+                    ));
+                }
+
+                true
+            }
+            MatchPattern::Object(parts) => {
+                let RuntimeValue::Object(obj) = value else {
+                    return Ok(false);
+                };
+
+                for (key, pattern) in parts {
+                    let Some(item) = obj.items.borrow().get(&key.value).cloned() else {
+                        return Ok(false);
+                    };
+
+                    if !self.match_pattern(pattern, &item, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                true
+            }
+            MatchPattern::Alternatives(patterns) => {
+                for pattern in patterns {
+                    // Failed alternatives should not leave their bindings behind
+                    let before = bindings.len();
+                    if self.match_pattern(pattern, value, bindings)? {
+                        return Ok(true);
+                    }
+                    bindings.truncate(before);
+                }
+
+                false
+            }
+        })
+    }
 }
@@ -1,3 +1,94 @@
-use super::Interpreter;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-impl Interpreter {}
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    parser::nodes::{self, CatchType},
+};
+
+use super::{
+    scope::{Scope, Variable},
+    values::{self, RuntimeValue, RuntimeValueUtils},
+    Interpreter, R,
+};
+
+impl Interpreter {
+    pub fn run_try(&mut self, expr: nodes::Try) -> R {
+        // Blocks that error do not swap their scope back, so remember it
+        let scope = self.scope.clone();
+
+        let result = match self.run(*expr.attempt) {
+            Ok(ok) => Ok(ok),
+            Err(err) if err.is_interrupt() => Err(err),
+            Err(err) => {
+                self.scope = scope;
+
+                match expr.catch {
+                    None => Ok(values::Null::new().wrap()),
+                    Some(CatchType::Value(value)) => self.run(*value),
+                    Some(CatchType::Binding(symbol, block)) => {
+                        let mut catch_scope = Scope::new_from_parent(self.scope.clone());
+                        catch_scope.insert(
+                            symbol.value,
+                            Variable::from(self.error_to_value(&err)),
+                            Some(symbol.location),
+                        )?;
+
+                        let old_scope = self.swap_scope(Rc::from(RefCell::from(catch_scope)));
+                        let result = self.run(*block);
+                        self.swap_scope(old_scope);
+                        result
+                    }
+                    Some(CatchType::Return) => {
+                        let result = self.global_scope.borrow().lookup("Result", None)?;
+                        let err_variant =
+                            self.member_check_basic(result, "Err".to_string(), None)?;
+                        let value = self.error_to_value(&err);
+                        let value =
+                            self.call_value(err_variant, vec![value], expr.location.clone())?;
+
+                        Err(ZephyrError {
+                            message: "Cannot return here".to_string(),
+                            code: ErrorCode::Return(Some(value)),
+                            location: Some(expr.location.clone()),
+                        })
+                    }
+                }
+            }
+        };
+
+        if let Some(finally) = expr.finally {
+            self.run(*finally)?;
+        }
+
+        result
+    }
+
+    pub fn run_throw(&mut self, expr: nodes::Throw) -> R {
+        let value = self.run(*expr.value)?;
+
+        Err(ZephyrError {
+            message: value.to_string(false, false, false)?,
+            code: ErrorCode::Thrown(value),
+            location: Some(expr.location),
+        })
+    }
+
+    /// Converts a caught error into the value given to catch
+    /// Thrown values are given as-is, interpreter errors become .{ code, message }
+    pub fn error_to_value(&self, err: &ZephyrError) -> RuntimeValue {
+        match err.code {
+            ErrorCode::Thrown(ref value) => value.clone(),
+            ref code => values::Object::new(HashMap::from([
+                (
+                    "code".to_string(),
+                    values::ZString::new(format!("{:?}", code)).wrap(),
+                ),
+                (
+                    "message".to_string(),
+                    values::ZString::new(err.message.clone()).wrap(),
+                ),
+            ]))
+            .wrap(),
+        }
+    }
+}
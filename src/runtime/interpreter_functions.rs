@@ -1,13 +1,15 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
-    errors::{ErrorCode, ZephyrError},
+    errors::{ErrorCode, TailCall, ZephyrError},
     lexer::tokens::Location,
     parser::nodes::{self, Node},
 };
 use crate::runtime::values::MspcSenderOptions;
 use super::{
+    gc,
     native::NativeExecutionContext,
+    profiler::Profiler,
     scope::{Scope, Variable},
     values::{self, FunctionType, RuntimeValue, RuntimeValueDetails, RuntimeValueUtils},
     Interpreter, R,
@@ -15,13 +17,17 @@ use super::{
 
 impl Interpreter {
     pub fn run_make_function(&mut self, expr: nodes::Function) -> R {
+        gc::track_scope(&self.scope);
         Ok(RuntimeValue::Function(values::Function {
             options: RuntimeValueDetails::default(),
             inner: values::FunctionInner {
-                body: expr.body,
+                body: Rc::from(expr.body),
                 name: expr.name.map(|x| x.value),
                 scope: self.scope.clone(),
-                arguments: expr.args.iter().map(|x| x.value.clone()).collect(),
+                arguments: Rc::from(expr.args),
+                is_generator: expr.is_generator,
+                is_async: expr.is_async,
+                chunk: None,
             },
         }))
     }
@@ -32,38 +38,87 @@ impl Interpreter {
         args: Vec<RuntimeValue>,
         location: Location,
     ) -> R {
+        self.run_function_with_named(func, args, HashMap::new(), location)
+    }
+
+    pub fn run_function_with_named(
+        &mut self,
+        func: FunctionType,
+        args: Vec<RuntimeValue>,
+        named: HashMap<String, RuntimeValue>,
+        location: Location,
+    ) -> R {
+        if !named.is_empty() && !matches!(func, FunctionType::Function(_)) {
+            return Err(ZephyrError {
+                code: ErrorCode::InvalidArgumentsError,
+                message: "Only Zephyr functions can be given named arguments".to_string(),
+                location: Some(location),
+            });
+        }
+
         match func {
             FunctionType::Function(func) => {
-                let mut scope = Scope::new_from_parent(func.inner.scope.clone());
-                for (i, v) in func.inner.arguments.iter().enumerate() {
-                    if i >= args.len() {
-                        scope.insert(
-                            v.clone(),
-                            Variable::from(values::Null::new().wrap()),
-                            Some(location.clone()),
-                        )?
-                    } else {
-                        scope.insert(
-                            v.clone(),
-                            Variable::from(args[i].clone()),
-                            Some(location.clone()),
-                        )?
-                    }
+                let depth = self.call_depth.get();
+                if depth >= self.max_call_depth {
+                    return Err(ZephyrError {
+                        code: ErrorCode::StackOverflow,
+                        message: format!("Maximum call depth of {} exceeded", self.max_call_depth),
+                        location: Some(location),
+                    });
                 }
+                self.call_depth.set(depth + 1);
+
+                let is_async = func.inner.is_async;
+                let (mut func, mut args, mut named, mut location) = (func, args, named, location);
+
+                // Calls which are returned are made here, so recursion which only returns calls
+                // does not grow the stack
+                let result = loop {
+                    match self.run_function_body(&func, args, named, &location) {
+                        Err(ZephyrError {
+                            code: ErrorCode::TailCall(call),
+                            location: call_location,
+                            ..
+                        }) => {
+                            let call = *call;
+                            let call_location = call_location.unwrap_or(location);
 
-                let old = self.swap_scope(Rc::from(RefCell::from(scope)));
-                let result = self.run(Node::Block(func.inner.body));
-                self.swap_scope(old);
+                            let Some(next) = Interpreter::tail_call_target(&call.function) else {
+                                break self.call_value_with_named(
+                                    call.function,
+                                    call.args,
+                                    call.named,
+                                    call_location,
+                                );
+                            };
 
-                if let Err(err) = &result {
-                    if let ErrorCode::Return(Some(val)) = &err.code {
-                        return Ok(val.clone());
-                    } else if let ErrorCode::Return(None) = &err.code {
-                        return Ok(values::Null::new().wrap());
+                            args = call.args;
+                            if let Some(val) = &call.function.options().proto_value {
+                                args.insert(0, *val.clone());
+                            }
+                            (func, named, location) = (next, call.named, call_location);
+                        }
+                        Err(ZephyrError {
+                            code: ErrorCode::Return(val),
+                            ..
+                        }) => break Ok(val.unwrap_or(values::Null::new().wrap())),
+                        result => break result,
                     }
+                };
+                self.call_depth.set(depth);
+
+                // The body has already ran, so the promise is settled straight away, a returned
+                // promise is given as-is rather than being wrapped in another
+                if is_async {
+                    let promise = match result {
+                        Ok(RuntimeValue::Promise(promise)) => promise,
+                        result => values::Promise::from_result(result),
+                    };
+                    self.track_rejection(&promise);
+                    return Ok(promise.wrap());
                 }
 
-                return result;
+                result
             }
             FunctionType::NativeFunction(func) => {
                 let ctx = NativeExecutionContext {
@@ -93,24 +148,312 @@ impl Interpreter {
         }
     }
 
+    /// Runs a Zephyr function's body once, giving what it returned as a Return or TailCall error
+    fn run_function_body(
+        &mut self,
+        func: &values::Function,
+        args: Vec<RuntimeValue>,
+        named: HashMap<String, RuntimeValue>,
+        location: &Location,
+    ) -> R {
+        if let Some(profiler) = &self.profiler {
+            profiler.borrow_mut().enter(Profiler::function_name(
+                &func.inner.name,
+                &func.inner.body.location,
+            ));
+        }
+
+        let scope = Scope::new_from_parent(func.inner.scope.clone());
+
+        // Defaults are ran inside the function's scope so they can see the previous arguments
+        let old = self.swap_scope(Rc::from(RefCell::from(scope)));
+        let result = match self.bind_arguments(&func.inner.arguments, args, named, location) {
+            Ok(_) if func.inner.is_generator => {
+                let scope = self.scope.clone();
+                Ok(self.make_generator((*func.inner.body).clone(), scope))
+            }
+            Ok(_) => match func.inner.chunk {
+                Some(ref chunk) => self.run_chunk(chunk),
+                None => self.run(Node::Block((*func.inner.body).clone())),
+            },
+            Err(err) => Err(err),
+        };
+        self.swap_scope(old);
+
+        if let Some(profiler) = &self.profiler {
+            profiler.borrow_mut().exit();
+        }
+
+        result
+    }
+
+    /// Gives the function a returned call can be made with in place of the function returning it
+    /// Natives, generators, async functions, structs and enum variants are called normally instead
+    fn tail_call_target(value: &RuntimeValue) -> Option<values::Function> {
+        let RuntimeValue::Function(func) = value else {
+            return None;
+        };
+
+        let tags = value.options().tags.borrow();
+        if func.inner.is_generator
+            || func.inner.is_async
+            || tags.contains_key("__struct")
+            || tags.contains_key("__enum_base")
+        {
+            return None;
+        }
+
+        Some(func.clone())
+    }
+
+    /// Defines the given arguments in the current scope based on the function's argument list
+    pub fn bind_arguments(
+        &mut self,
+        arguments: &nodes::Arguments,
+        args: Vec<RuntimeValue>,
+        mut named: HashMap<String, RuntimeValue>,
+        location: &Location,
+    ) -> Result<(), ZephyrError> {
+        if arguments.positional_rest.is_none() && args.len() > arguments.positional.len() {
+            return Err(ZephyrError {
+                code: ErrorCode::InvalidArgumentsError,
+                message: format!(
+                    "Expected at most {} arguments, but got {}",
+                    arguments.positional.len(),
+                    args.len()
+                ),
+                location: Some(location.clone()),
+            });
+        }
+
+        let mut args = args.into_iter();
+
+        for argument in &arguments.positional {
+            let value = match args.next() {
+                Some(value) => value,
+                None => match argument.default {
+                    Some(ref default) => self.run(*default.clone())?,
+                    None => {
+                        return Err(ZephyrError {
+                            code: ErrorCode::InvalidArgumentsError,
+                            message: format!("Missing argument {}", argument.name.value),
+                            location: Some(location.clone()),
+                        })
+                    }
+                },
+            };
+
+            self.scope.borrow_mut().insert(
+                argument.name.value.clone(),
+                Variable::from(value),
+                Some(argument.name.location.clone()),
+            )?;
+        }
+
+        if let Some(ref rest) = arguments.positional_rest {
+            self.scope.borrow_mut().insert(
+                rest.value.clone(),
+                Variable::from(values::Array::new(args.collect()).wrap()),
+                Some(rest.location.clone()),
+            )?;
+        }
+
+        for argument in &arguments.named {
+            let value = match named.remove(&argument.name.value) {
+                Some(value) => value,
+                None => match argument.default {
+                    Some(ref default) => self.run(*default.clone())?,
+                    None => {
+                        return Err(ZephyrError {
+                            code: ErrorCode::InvalidArgumentsError,
+                            message: format!("Missing named argument {}", argument.name.value),
+                            location: Some(location.clone()),
+                        })
+                    }
+                },
+            };
+
+            self.scope.borrow_mut().insert(
+                argument.name.value.clone(),
+                Variable::from(value),
+                Some(argument.name.location.clone()),
+            )?;
+        }
+
+        if let Some(ref rest) = arguments.named_rest {
+            self.scope.borrow_mut().insert(
+                rest.value.clone(),
+                Variable::from(values::Object::new(named).wrap()),
+                Some(rest.location.clone()),
+            )?;
+        } else if let Some(name) = named.keys().next() {
+            return Err(ZephyrError {
+                code: ErrorCode::InvalidArgumentsError,
+                message: format!("Unknown named argument {}", name),
+                location: Some(location.clone()),
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn run_call(&mut self, expr: nodes::Call) -> R {
-        let left = self.run(*expr.left.clone())?;
+        match self.run_chain_left(*expr.left.clone(), expr.optional)? {
+            Some(left) => self.call_of(left, expr),
+            None => Ok(values::Null::new().wrap()),
+        }
+    }
+
+    /// Evaluates the arguments of a call and calls the already evaluated left with them
+    pub fn call_of(&mut self, left: RuntimeValue, expr: nodes::Call) -> R {
+        let location = expr.location.clone();
+        let (args, named) = self.call_arguments(expr)?;
+        self.call_value_with_named(left, args, named, location)
+    }
+
+    fn call_arguments(
+        &mut self,
+        expr: nodes::Call,
+    ) -> Result<(Vec<RuntimeValue>, HashMap<String, RuntimeValue>), ZephyrError> {
+        let args = self.run_spreadable(expr.args)?;
+
+        let mut named: HashMap<String, RuntimeValue> = HashMap::new();
+        for (name, arg) in expr.named_args {
+            named.insert(name.value, self.run(arg)?);
+        }
+
+        Ok((args, named))
+    }
+
+    /// Evaluates a returned call without making it, leaving that to the function it returns from
+    pub fn run_tail_call(&mut self, expr: nodes::Call) -> R {
+        let location = expr.location.clone();
+        let Some(left) = self.run_chain_left(*expr.left.clone(), expr.optional)? else {
+            return Err(ZephyrError {
+                message: "Cannot return here".to_string(),
+                code: ErrorCode::Return(Some(values::Null::new().wrap())),
+                location: Some(location),
+            });
+        };
+        let (args, named) = self.call_arguments(expr)?;
+
+        Err(ZephyrError {
+            message: "Cannot return here".to_string(),
+            code: ErrorCode::TailCall(Box::new(TailCall {
+                function: left,
+                args,
+                named,
+            })),
+            location: Some(location),
+        })
+    }
 
-        let mut args: Vec<RuntimeValue> = vec![];
-        for arg in expr.args {
-            args.push(self.run(arg)?);
+    /// Maps the arguments given to a shaped enum variant or struct onto its fields
+    pub fn map_fields(
+        &mut self,
+        fields: &[String],
+        args: Vec<RuntimeValue>,
+        mut named: HashMap<String, RuntimeValue>,
+        name: &str,
+        location: &Location,
+    ) -> R {
+        if args.len() > fields.len() {
+            return Err(ZephyrError {
+                code: ErrorCode::InvalidArgumentsError,
+                message: format!(
+                    "{} expects {} arguments, got {}",
+                    name,
+                    fields.len(),
+                    args.len()
+                ),
+                location: Some(location.clone()),
+            });
         }
 
+        let mut items: HashMap<String, RuntimeValue> = HashMap::new();
+        let mut args = args.into_iter();
+
+        for field in fields {
+            let value = match (args.next(), named.remove(field)) {
+                (Some(_), Some(_)) => {
+                    return Err(ZephyrError {
+                        code: ErrorCode::InvalidArgumentsError,
+                        message: format!("{} was given {} twice", name, field),
+                        location: Some(location.clone()),
+                    })
+                }
+                (Some(value), None) | (None, Some(value)) => value,
+                (None, None) => {
+                    return Err(ZephyrError {
+                        code: ErrorCode::InvalidArgumentsError,
+                        message: format!("{} is missing {}", name, field),
+                        location: Some(location.clone()),
+                    })
+                }
+            };
+
+            items.insert(field.clone(), value);
+        }
+
+        if let Some(key) = named.keys().next() {
+            return Err(ZephyrError {
+                code: ErrorCode::InvalidArgumentsError,
+                message: format!("{} does not have a field named {}", name, key),
+                location: Some(location.clone()),
+            });
+        }
+
+        Ok(values::Object::new(items).wrap())
+    }
+
+    /// Calls an already evaluated value, used by run_call and anything else that needs to call a value
+    pub fn call_value(
+        &mut self,
+        left: RuntimeValue,
+        args: Vec<RuntimeValue>,
+        location: Location,
+    ) -> R {
+        self.call_value_with_named(left, args, HashMap::new(), location)
+    }
+
+    pub fn call_value_with_named(
+        &mut self,
+        left: RuntimeValue,
+        mut args: Vec<RuntimeValue>,
+        named: HashMap<String, RuntimeValue>,
+        location: Location,
+    ) -> R {
         if let Some(val) = &left.options().proto_value {
             args.insert(0, *val.clone());
         }
 
-        if let Some(enum_id) = left.options().tags.borrow_mut().get("__enum_base").cloned() {
-            if args.len() > 1 {
+        if let Some(proto) = left.options().tags.borrow().get("__struct").cloned() {
+            return self.construct(&left, proto, args, named, location);
+        }
+
+        if let Some(enum_id) = left.options().tags.borrow().get("__enum_base").cloned() {
+            // Variants with a declared shape hold an object of their fields
+            if let Some(fields) = left.options().tags.borrow().get("__enum_fields") {
+                let fields = fields
+                    .split(',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>();
+                let name = values::EnumVariant::name_of_id(&enum_id);
+                let inner = self.map_fields(&fields, args, named, name, &location)?;
+
+                let mut variant = values::EnumVariant::new(inner, enum_id.clone());
+                variant.fields = Some(fields);
+                return Ok(variant
+                    .wrap()
+                    .set_proto(left.options().proto.borrow().clone().unwrap()));
+            }
+
+            if args.len() > 1 || !named.is_empty() {
                 return Err(ZephyrError {
                     code: ErrorCode::TypeError,
                     message: "Expected 1 or 0 arguments for enum variant".to_string(),
-                    location: Some(expr.location.clone()),
+                    location: Some(location.clone()),
                 });
             }
 
@@ -133,20 +476,28 @@ impl Interpreter {
 
         match left {
             RuntimeValue::Function(func) => {
-                self.run_function(FunctionType::Function(func), args, expr.location)
-            }
-            RuntimeValue::NativeFunction(func) => {
-                self.run_function(FunctionType::NativeFunction(func), args, expr.location)
+                self.run_function_with_named(FunctionType::Function(func), args, named, location)
             }
+            RuntimeValue::NativeFunction(func) => self.run_function_with_named(
+                FunctionType::NativeFunction(func),
+                args,
+                named,
+                location,
+            ),
             RuntimeValue::MspcSender(func) => {
-                self.run_function(FunctionType::MspcSender(func), args, expr.location)
+                self.run_function_with_named(FunctionType::MspcSender(func), args, named, location)
             }
             _ => {
+                if let Some(func) = self.find_overload(&left, "__call") {
+                    args.insert(0, left);
+                    return self.call_value_with_named(func, args, named, location);
+                }
+
                 return Err(ZephyrError {
                     code: ErrorCode::InvalidOperation,
                     message: format!("Cannot call a {}", left.type_name()),
-                    location: Some(expr.left.location().clone()),
-                })
+                    location: Some(location),
+                });
             }
         }
     }
@@ -0,0 +1,328 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    lexer::tokens::Location,
+    parser::nodes::{self, Node},
+};
+
+use super::{
+    interpreter_iterators::ValueIter,
+    scope::{Scope, ScopeInnerType, Variable},
+    values::{self, RuntimeValue, RuntimeValueUtils},
+    Interpreter, R,
+};
+
+/// The suspended state of a generator
+///
+/// The body can not be paused in the middle of Interpreter::run, so blocks, ifs and loops in the body
+/// are ran one statement at a time using these frames instead, letting yield stop between statements
+pub struct GeneratorState {
+    pub frames: Vec<GeneratorFrame>,
+    pub scope: ScopeInnerType,
+    pub done: bool,
+}
+
+pub enum GeneratorFrame {
+    /// Statements to run in order, restoring outer as the scope once finished
+    Block {
+        nodes: Vec<Node>,
+        index: usize,
+        outer: Option<ScopeInnerType>,
+    },
+    While(nodes::WhileLoop),
+    For {
+        expr: Box<nodes::For>,
+        iter: ValueIter,
+        index: usize,
+    },
+}
+
+impl Interpreter {
+    /// Creates the generator for a call to a func*, the arguments must already be bound in scope
+    pub fn make_generator(&mut self, body: nodes::Block, scope: ScopeInnerType) -> RuntimeValue {
+        values::Generator::new(GeneratorState {
+            frames: vec![GeneratorFrame::Block {
+                nodes: body.nodes,
+                index: 0,
+                outer: None,
+            }],
+            scope,
+            done: false,
+        })
+        .wrap()
+    }
+
+    /// Runs a generator until its next yield, giving .{ value, done }
+    pub fn generator_next(&mut self, generator: &values::Generator, location: &Location) -> R {
+        let Ok(mut generator) = generator.state.try_borrow_mut() else {
+            return Err(ZephyrError {
+                message: "Cannot resume a generator which is already running".to_string(),
+                code: ErrorCode::InvalidOperation,
+                location: Some(location.clone()),
+            });
+        };
+
+        let result = if generator.done {
+            Ok(None)
+        } else {
+            let caller = self.swap_scope(generator.scope.clone());
+            let result = self.resume_generator(&mut generator, location);
+            generator.scope = self.swap_scope(caller);
+            result
+        };
+
+        let (value, done) = match result {
+            Ok(Some(value)) => (value, false),
+            Ok(None) => (values::Null::new().wrap(), true),
+            Err(err) => match err.code {
+                ErrorCode::Return(value) => (value.unwrap_or(values::Null::new().wrap()), true),
+                _ => {
+                    generator.done = true;
+                    generator.frames.clear();
+                    return Err(err);
+                }
+            },
+        };
+
+        if done {
+            generator.done = true;
+            generator.frames.clear();
+        }
+
+        Ok(values::Object::new(HashMap::from([
+            ("value".to_string(), value),
+            ("done".to_string(), values::Boolean::new(done).wrap()),
+        ]))
+        .wrap())
+    }
+
+    /// Runs the frames of a generator, giving the yielded value, or None once the body has finished
+    fn resume_generator(
+        &mut self,
+        generator: &mut GeneratorState,
+        location: &Location,
+    ) -> Result<Option<RuntimeValue>, ZephyrError> {
+        loop {
+            let step = match generator.frames.last_mut() {
+                None => return Ok(None),
+                Some(GeneratorFrame::Block {
+                    nodes,
+                    index,
+                    outer,
+                }) => {
+                    if let Some(node) = nodes.get(*index).cloned() {
+                        *index += 1;
+                        self.generator_statement(generator, node)
+                    } else {
+                        if let Some(outer) = outer.clone() {
+                            self.scope = outer;
+                        }
+                        generator.frames.pop();
+                        Ok(None)
+                    }
+                }
+                Some(GeneratorFrame::While(expr)) => {
+                    let body = *expr.body.clone();
+                    match self.run(*expr.test.clone()) {
+                        Ok(test) if test.is_truthy() => {
+                            generator.frames.push(GeneratorFrame::Block {
+                                nodes: vec![body],
+                                index: 0,
+                                outer: None,
+                            });
+                            Ok(None)
+                        }
+                        Ok(_) => {
+                            generator.frames.pop();
+                            Ok(None)
+                        }
+                        Err(err) => Err(err),
+                    }
+                }
+                Some(GeneratorFrame::For { expr, iter, index }) => {
+                    let iterator_location = expr.iterator.location().clone();
+                    match self.iter_next(iter, &iterator_location) {
+                        Ok(Some(value)) => {
+                            let mut scope = Scope::new_from_parent(self.scope.clone());
+                            scope.insert(
+                                expr.index_symbol.value.clone(),
+                                Variable::from(values::Number::new(*index as f64).wrap()),
+                                Some(expr.index_symbol.location.clone()),
+                            )?;
+                            if let Some(ref symbol) = expr.value_symbol {
+                                scope.insert(
+                                    symbol.value.clone(),
+                                    Variable::from(value),
+                                    Some(symbol.location.clone()),
+                                )?;
+                            }
+                            *index += 1;
+
+                            let body = *expr.block.clone();
+                            let outer = self.swap_scope(Rc::from(RefCell::from(scope)));
+                            generator.frames.push(GeneratorFrame::Block {
+                                nodes: vec![body],
+                                index: 0,
+                                outer: Some(outer),
+                            });
+                            Ok(None)
+                        }
+                        Ok(None) => {
+                            generator.frames.pop();
+                            Ok(None)
+                        }
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+
+            match step {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => (),
+                Err(err) => self.unwind_generator(generator, err, location)?,
+            }
+        }
+    }
+
+    /// Runs a single statement of a generator body, giving Some if it yielded
+    fn generator_statement(
+        &mut self,
+        generator: &mut GeneratorState,
+        node: Node,
+    ) -> Result<Option<RuntimeValue>, ZephyrError> {
+        match node {
+            Node::Yield(expr) => Ok(Some(match expr.value {
+                Some(value) => self.run(*value)?,
+                None => values::Null::new().wrap(),
+            })),
+            Node::Block(block) => {
+                let outer = self.swap_scope(Rc::from(RefCell::from(Scope::new_from_parent(
+                    self.scope.clone(),
+                ))));
+                generator.frames.push(GeneratorFrame::Block {
+                    nodes: block.nodes,
+                    index: 0,
+                    outer: Some(outer),
+                });
+                Ok(None)
+            }
+            Node::If(expr) => {
+                let branch = if self.run(*expr.test)?.is_truthy() {
+                    Some(*expr.succss)
+                } else {
+                    expr.alternate.map(|x| *x)
+                };
+
+                if let Some(branch) = branch {
+                    generator.frames.push(GeneratorFrame::Block {
+                        nodes: vec![branch],
+                        index: 0,
+                        outer: None,
+                    });
+                }
+                Ok(None)
+            }
+            Node::WhileLoop(expr) => {
+                generator.frames.push(GeneratorFrame::While(expr));
+                Ok(None)
+            }
+            Node::For(expr) => {
+                let location = expr.iterator.location().clone();
+                let value = self.run(*expr.iterator.clone())?;
+                let iter = self.make_iter(value, &location)?;
+                generator.frames.push(GeneratorFrame::For {
+                    expr: Box::new(expr),
+                    iter,
+                    index: 0,
+                });
+                Ok(None)
+            }
+            node => {
+                self.run(node)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Handles an error from a generator's body, break and continue are given to their loop frame,
+    /// anything else finishes the generator
+    fn unwind_generator(
+        &mut self,
+        generator: &mut GeneratorState,
+        err: ZephyrError,
+        location: &Location,
+    ) -> Result<(), ZephyrError> {
+        if !matches!(err.code, ErrorCode::Break(_) | ErrorCode::Continue(_)) {
+            return Err(ZephyrError {
+                location: err.location.or(Some(location.clone())),
+                ..err
+            });
+        }
+
+        while let Some(frame) = generator.frames.pop() {
+            let (label, on_break) = match frame {
+                GeneratorFrame::Block { outer, .. } => {
+                    if let Some(outer) = outer {
+                        self.scope = outer;
+                    }
+                    continue;
+                }
+                GeneratorFrame::While(ref expr) => (expr.label.clone(), expr.on_break.clone()),
+                GeneratorFrame::For { ref expr, .. } => (expr.label.clone(), expr.on_break.clone()),
+            };
+
+            // An error means it was for an outer loop
+            if let Ok(broken) = Interpreter::loop_interrupt(err.clone(), &label) {
+                if broken {
+                    self.run_loop_else(true, on_break)?;
+                } else {
+                    generator.frames.push(frame);
+                }
+
+                return Ok(());
+            }
+        }
+
+        Err(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::test::run;
+
+    #[test]
+    fn generators() {
+        let cases = [
+            ("func* g() { yield 1; yield 2 } [...g()]", "[1, 2]"),
+            (
+                "func* g(n) { for i in 0..n { if i == 2 { continue } yield i } } [...g(4)]",
+                "[0, 1, 3]",
+            ),
+            (
+                "func* g() { yield 1; return 5 } let x = g(); x.next(); x.next().value",
+                "5",
+            ),
+            (
+                "func* g() { yield 1 } let x = g(); x.next(); x.next(); x.next().done",
+                "true",
+            ),
+            // Each call gets its own state
+            (
+                "func* g() { yield 1; yield 2 } let a = g(); a.next(); g().next().value",
+                "1",
+            ),
+            (
+                "let x = null; func* g() { x.next(); yield 1 } x = g(); x.next()",
+                "error: Cannot resume a generator which is already running",
+            ),
+        ];
+
+        for (code, expected) in cases {
+            for use_vm in [false, true] {
+                assert_eq!(run(code, use_vm), expected, "{}", code);
+            }
+        }
+    }
+}
@@ -1,9 +1,15 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::parser::nodes;
+use crate::{
+    errors::ZephyrError,
+    parser::{
+        nodes::{self, Node},
+        resolver::Resolver,
+    },
+};
 
 use super::{
-    scope::Scope,
+    scope::{Scope, ScopeInnerType},
     values::{self, RuntimeValueUtils},
     Interpreter, R,
 };
@@ -17,7 +23,13 @@ impl Interpreter {
         let mut last_executed = values::Null::new().wrap();
 
         for i in expr.nodes {
-            last_executed = self.run(i)?;
+            last_executed = match self.run(i) {
+                Ok(ok) => ok,
+                Err(err) => {
+                    self.swap_scope(old_scope);
+                    return Err(err);
+                }
+            };
         }
 
         self.swap_scope(old_scope);
@@ -33,4 +45,26 @@ impl Interpreter {
 
         Ok(last_executed)
     }
+
+    /// Resolves the variables of a node which is going to be ran in the scope
+    pub fn resolve(node: &mut Node, scope: &ScopeInnerType) -> Result<(), ZephyrError> {
+        let mut scopes: Vec<Vec<String>> = vec![];
+        let mut current = Some(scope.clone());
+
+        while let Some(scope) = current {
+            let lock = scope.borrow();
+            scopes.push(lock.slot_names());
+            current = lock.parent.clone();
+        }
+
+        scopes.reverse();
+        let mut resolver = Resolver::new(scopes);
+        resolver.resolve(node)?;
+
+        for warning in resolver.warnings {
+            eprintln!("{}", warning.visualise_warning());
+        }
+
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@ use std::{cell::RefCell, collections::HashMap, fs, path::PathBuf, rc::Rc};
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
-    lexer::lexer::lex,
+    lexer::{lexer::lex, tokens::Location},
     parser::{
         nodes::{self, DeclareType, ExportType, ExposeType, Node},
         Parser,
@@ -10,7 +10,7 @@ use crate::{
 };
 
 use super::{
-    scope::{Scope, Variable},
+    scope::{Scope, ScopeInnerType, Variable},
     values::{self, RuntimeValueUtils},
     Interpreter, Module, R,
 };
@@ -39,117 +39,177 @@ impl Interpreter {
                     }
                 };
             }
-            _ => panic!("Cannot handle this yet"),
+            ExportType::Object(items) => {
+                let mut lock = self.scope.borrow_mut();
+                for (symbol, export_as) in items {
+                    lock.exported.insert(symbol.value, export_as);
+                }
+            }
+            ExportType::From(exposing, from) => {
+                let (path, scope, loaded) = self.load_module(from, &node.location)?;
+                self.expose_module(&path, scope, loaded, exposing, true, &node.location)?;
+            }
         };
 
         Ok(values::Null::new().wrap())
     }
 
     pub fn run_import(&mut self, node: nodes::Import) -> R {
+        let (path, scope, loaded) = self.load_module(node.import, &node.location)?;
+        self.expose_module(&path, scope, loaded, node.exposing, false, &node.location)?;
+
+        Ok(values::Null::new().wrap())
+    }
+
+    /// Resolves, runs and caches a module, giving back (path, module scope, has finished loading)
+    pub fn load_module(
+        &mut self,
+        import: String,
+        location: &Location,
+    ) -> Result<(String, ScopeInnerType, bool), ZephyrError> {
         // Resolve file location
-        let path = &if PathBuf::from(node.import.clone()).is_absolute() {
-            PathBuf::from(node.import.clone())
+        let path = &if PathBuf::from(import.clone()).is_absolute() {
+            PathBuf::from(import.clone())
         } else {
             let _path = &PathBuf::from(self.scope.borrow().file_name.clone())
                 .parent()
                 .unwrap()
-                .join(node.import.clone());
+                .join(import.clone());
             fs::canonicalize(_path).map_err(|_| ZephyrError {
                 message: format!("Cannot resolve {}", _path.display().to_string()),
                 code: ErrorCode::CannotResolve,
-                location: Some(node.location.clone()),
+                location: Some(location.clone()),
             })?
         }
         .display()
         .to_string();
 
-        let scope = if let Some(cache) = self.module_cache.get(path) {
-            // The module is in module cache and is awaiting to be loaded
-            (cache.borrow().scope.clone(), false)
-        } else {
-            let read = fs::read_to_string(path).map_err(|err| ZephyrError {
-                message: format!("Cannot read {}: {}", path, err.kind()),
-                code: ErrorCode::CannotResolve,
-                location: Some(node.location.clone()),
-            })?;
-
-            let lexd = lex(&read, path.to_string())?;
-            let ast = match Parser::new(lexd, path.to_string()).produce_ast()? {
-                Node::Block(block) => Node::ExportedBlock(nodes::ExportedBlock {
-                    nodes: block.nodes,
-                    location: block.location,
-                }),
-                _ => unreachable!(),
-            };
-            let scope = Rc::from(RefCell::from(Scope::new_from_parent(
-                self.global_scope.clone(),
-            )));
-
-            let module = Rc::from(RefCell::from(Module {
-                scope: scope.clone(),
-                wanted: vec![],
-                exports: HashMap::new(),
-            }));
-
-            self.module_cache.insert(path.to_string(), module.clone());
-
-            let old_scope = self.swap_scope(scope.clone());
-            self.run(ast)?;
-            self.swap_scope(old_scope);
-
-            // Check all the places that have tried to import from this module
-            let wanted = module.borrow().wanted.clone();
-            for i in wanted {
-                if !scope.borrow().exported.contains_key(&i.0) {
-                    return Err(ZephyrError {
-                        message: format!("Module {} does not export {}", path, i.0.clone()),
-                        code: ErrorCode::NotExported,
-                        location: Some(node.location.clone()),
-                    });
-                }
-            }
+        if let Some(cache) = self.module_cache.get(path) {
+            // The module is in module cache, it may still be awaiting to be loaded
+            let cache = cache.borrow();
+            return Ok((path.clone(), cache.scope.clone(), cache.loaded));
+        }
 
-            (scope, true)
+        let read = fs::read_to_string(path).map_err(|err| ZephyrError {
+            message: format!("Cannot read {}: {}", path, err.kind()),
+            code: ErrorCode::CannotResolve,
+            location: Some(location.clone()),
+        })?;
+
+        let lexd = lex(&read, path.to_string())?;
+        let mut ast = match Parser::new(lexd, path.to_string()).produce_ast()? {
+            Node::Block(block) => Node::ExportedBlock(nodes::ExportedBlock {
+                nodes: block.nodes,
+                location: block.location,
+            }),
+            _ => unreachable!(),
         };
+        let scope = Rc::from(RefCell::from(Scope::new_from_parent(
+            self.global_scope.clone(),
+        )));
+        Interpreter::resolve(&mut ast, &scope)?;
+
+        let module = Rc::from(RefCell::from(Module {
+            scope: scope.clone(),
+            wanted: vec![],
+            exports: HashMap::new(),
+            loaded: false,
+        }));
+
+        self.module_cache.insert(path.to_string(), module.clone());
+
+        let old_scope = self.swap_scope(scope.clone());
+        let result = self.run(ast);
+        self.swap_scope(old_scope);
+        result?;
+        module.borrow_mut().loaded = true;
+
+        // Check all the places that have tried to import from this module
+        let wanted = module.borrow().wanted.clone();
+        for (name, location) in wanted {
+            if scope.borrow().resolve_export(&name).is_none() {
+                return Err(ZephyrError {
+                    message: format!("Module {} does not export {}", path, name),
+                    code: ErrorCode::NotExported,
+                    location: Some(location),
+                });
+            }
+        }
 
-        // Define module export pointers
-        for expose in node.exposing {
-            let t = match expose {
-                ExposeType::Identifier(i) => (Some(i.clone()), i.clone()),
-                ExposeType::IdentifierAs(i, a) => (Some(i), a),
-                ExposeType::StarAs(a) => (None, a),
-                _ => panic!(),
+        Ok((path.clone(), scope, true))
+    }
+
+    /// Defines module export pointers in the current scope, also exporting them if re_export is set
+    pub fn expose_module(
+        &mut self,
+        path: &str,
+        scope: ScopeInnerType,
+        loaded: bool,
+        exposing: Vec<ExposeType>,
+        re_export: bool,
+        location: &Location,
+    ) -> Result<(), ZephyrError> {
+        // (name in the module, name in this scope, location)
+        let mut parts: Vec<(Option<String>, String, Location)> = vec![];
+
+        for expose in exposing {
+            match expose {
+                ExposeType::Identifier(i) => {
+                    parts.push((Some(i.value.clone()), i.value, i.location))
+                }
+                ExposeType::IdentifierAs(i, a) => parts.push((Some(i.value), a, i.location)),
+                ExposeType::StarAs(a) => parts.push((None, a, location.clone())),
+                ExposeType::Star() => {
+                    if !loaded {
+                        return Err(ZephyrError {
+                            message: format!(
+                                "Cannot expose * from {} as it has not finished loading, fix the cyclic dependency",
+                                path
+                            ),
+                            code: ErrorCode::Unresolved,
+                            location: Some(location.clone()),
+                        });
+                    }
+
+                    for name in scope.borrow().exported_names() {
+                        parts.push((Some(name.clone()), name, location.clone()));
+                    }
+                }
             };
+        }
 
-            // Check if module actually exports it
-            if scope.1 {
-                if let Some(ref name) = t.0 {
-                    if !scope.0.borrow().variables.contains_key(name) {
+        for (name, local, location) in parts {
+            if let Some(ref name) = name {
+                // Check if module actually exports it
+                if loaded {
+                    if scope.borrow().resolve_export(name).is_none() {
                         return Err(ZephyrError {
-                            message: format!("Module {} does not export {}", path, name.clone()),
+                            message: format!("Module {} does not export {}", path, name),
                             code: ErrorCode::NotExported,
-                            location: Some(node.location.clone()),
+                            location: Some(location),
                         });
                     }
+                } else {
+                    self.module_cache
+                        .get(path)
+                        .unwrap()
+                        .borrow_mut()
+                        .wanted
+                        .push((name.clone(), location.clone()));
                 }
             }
 
-            if !scope.1 {
-                self.module_cache
-                    .get(path)
-                    .unwrap()
-                    .borrow_mut()
-                    .wanted
-                    .push((t.1.clone(), node.location.clone()));
-            }
-
             self.scope.borrow_mut().insert(
-                t.1,
-                Variable::from(values::Export::new(scope.0.clone(), t.0).wrap()),
-                Some(node.location.clone()),
+                local.clone(),
+                Variable::from(values::Export::new(scope.clone(), name).wrap()),
+                Some(location),
             )?;
+
+            if re_export {
+                self.scope.borrow_mut().exported.insert(local, None);
+            }
         }
 
-        return Ok(values::Null::new().wrap());
+        Ok(())
     }
 }
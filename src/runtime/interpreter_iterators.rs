@@ -0,0 +1,235 @@
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    lexer::tokens::Location,
+};
+
+use super::{
+    values::{self, RuntimeValue, RuntimeValueUtils},
+    Interpreter,
+};
+
+/// A lazy iterator over a value, values are only made when next is called
+pub enum ValueIter {
+    /// Values which already exist, such as the items of an array
+    Items(std::vec::IntoIter<RuntimeValue>),
+    /// The numbers of a range, last may be infinite
+    Range {
+        start: f64,
+        step: f64,
+        last: f64,
+        index: usize,
+    },
+    /// A Zephyr iterator, next() is called until it gives .{ done: true }
+    Protocol(Box<RuntimeValue>),
+}
+
+impl Interpreter {
+    /// Makes an iterator for a value
+    /// - Ranges are lazy
+    /// - Values which define __iter are iterated over using what it gives
+    /// - Values whose next is a function are their own iterator
+    /// - Anything else is iterated over using its items
+    pub fn make_iter(
+        &mut self,
+        value: RuntimeValue,
+        location: &Location,
+    ) -> Result<ValueIter, ZephyrError> {
+        if let RuntimeValue::RangeValue(ref range) = value {
+            let (start, step, last) = range.bounds().map_err(|err| ZephyrError {
+                location: Some(location.clone()),
+                ..err
+            })?;
+
+            return Ok(ValueIter::Range {
+                start,
+                step,
+                last,
+                index: 0,
+            });
+        }
+
+        if let Some(iterator) = self.call_overload(&value, "__iter", vec![], location)? {
+            if self.find_next(&iterator).is_none() {
+                return Err(ZephyrError {
+                    message: format!("__iter must give an iterator, got {}", iterator.type_name()),
+                    code: ErrorCode::CannotIterate,
+                    location: Some(location.clone()),
+                });
+            }
+
+            return Ok(ValueIter::Protocol(Box::new(iterator)));
+        }
+
+        if let Some(RuntimeValue::Function(_) | RuntimeValue::NativeFunction(_)) =
+            self.find_next(&value)
+        {
+            return Ok(ValueIter::Protocol(Box::new(value)));
+        }
+
+        Ok(ValueIter::Items(
+            value
+                .iter()
+                .map_err(|err| ZephyrError {
+                    location: Some(location.clone()),
+                    ..err
+                })?
+                .into_iter(),
+        ))
+    }
+
+    /// Gets the next of a value, either as a property or via its prototype
+    ///
+    /// Anything given by __iter with a next is an iterator, but other values are only treated as
+    /// one if their next is a function, so objects which just hold data under next are not
+    fn find_next(&self, value: &RuntimeValue) -> Option<RuntimeValue> {
+        match value {
+            RuntimeValue::Object(obj) => obj.items.borrow().get("next").cloned(),
+            _ => None,
+        }
+        .or_else(|| self.find_overload(value, "next"))
+    }
+
+    /// Gets the next value of an iterator, None once it has finished
+    pub fn iter_next(
+        &mut self,
+        iter: &mut ValueIter,
+        location: &Location,
+    ) -> Result<Option<RuntimeValue>, ZephyrError> {
+        match iter {
+            ValueIter::Items(items) => Ok(items.next()),
+            ValueIter::Range {
+                start,
+                step,
+                last,
+                index,
+            } => {
+                let value = *start + *index as f64 * *step;
+                if (*step > 0.0 && value <= *last) || (*step < 0.0 && value >= *last) {
+                    *index += 1;
+                    Ok(Some(values::Number::new(value).wrap()))
+                } else {
+                    Ok(None)
+                }
+            }
+            ValueIter::Protocol(iterator) => {
+                let next = self.member_check_basic(*iterator.clone(), "next".to_string(), None)?;
+                let result = self.call_value(next, vec![], location.clone())?;
+
+                let RuntimeValue::Object(ref obj) = result else {
+                    return Err(ZephyrError {
+                        message: format!(
+                            "next must give .{{ value, done }}, got {}",
+                            result.type_name()
+                        ),
+                        code: ErrorCode::TypeError,
+                        location: Some(location.clone()),
+                    });
+                };
+
+                let items = obj.items.borrow();
+                if items.get("done").is_some_and(|x| x.is_truthy()) {
+                    return Ok(None);
+                }
+
+                Ok(Some(
+                    items
+                        .get("value")
+                        .cloned()
+                        .unwrap_or_else(|| values::Null::new().wrap()),
+                ))
+            }
+        }
+    }
+
+    /// Runs an iterator to the end, giving all of its values
+    pub fn collect_iter(
+        &mut self,
+        value: RuntimeValue,
+        location: &Location,
+    ) -> Result<Vec<RuntimeValue>, ZephyrError> {
+        let mut iter = self.make_iter(value, location)?;
+        let mut items: Vec<RuntimeValue> = vec![];
+
+        if let ValueIter::Range { last, .. } = iter {
+            if last.is_infinite() {
+                return Err(ZephyrError {
+                    message: "Cannot collect an infinite range, loop over it instead".to_string(),
+                    code: ErrorCode::RangeError,
+                    location: Some(location.clone()),
+                });
+            }
+        }
+
+        while let Some(item) = self.iter_next(&mut iter, location)? {
+            items.push(item);
+        }
+
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::test::run;
+
+    const COUNTER: &str = "struct Counter(to) {
+        func __iter(self) {
+            let i = 0;
+            let to = self.to;
+            .{ next: func { i += 1; if i > to { .{ done: true } } else { .{ value: i } } } }
+        }
+    }";
+
+    #[test]
+    fn protocol() {
+        let cases = [
+            ("[...Counter(3)]", "[1, 2, 3]"),
+            ("let t = 0; for i, v in Counter(4) { t += i * v } t", "20"),
+            ("let [a, b] = Counter(5); a + b", "3"),
+            ("iter(Counter(2))", "[1, 2]"),
+            // Taking values from an iterator which never finishes
+            ("let n = 0; for v in .{ next: func { n += 1; .{ value: n } } } { if n == 10 { break } } n", "10"),
+        ];
+
+        for (code, expected) in cases {
+            assert_eq!(
+                run(&format!("{} {}", COUNTER, code), false),
+                expected,
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn ranges_are_lazy() {
+        assert_eq!(
+            run(
+                "let last = 0; for v in 0..Math.INFINITY { last = v; if v == 1000 { break } } last",
+                false
+            ),
+            "1000"
+        );
+        assert_eq!(run("[...(0..=10:5)]", false), "[0, 5, 10]");
+    }
+
+    #[test]
+    fn not_iterators() {
+        // next has to be a function, unless the value was given by __iter
+        assert_eq!(
+            run("for x in .{ next: 1 } { }", false),
+            "error: Cannot iter a object"
+        );
+        assert_eq!(
+            run(
+                "struct S { func __iter(self) { 1 } } for x in S() { }",
+                false
+            ),
+            "error: __iter must give an iterator, got number"
+        );
+        assert_eq!(
+            run("[....{ next: func { 1 } }]", false),
+            "error: next must give .{ value, done }, got number"
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
-    parser::nodes,
+    parser::nodes::{self, Node, ObjectItem},
 };
 
 use super::{
@@ -12,18 +12,64 @@ use super::{
 
 impl Interpreter {
     pub fn run_array(&mut self, expr: nodes::Array) -> R {
+        let items = self.run_spreadable(expr.items)?;
+        Ok(values::Array::new(items).wrap())
+    }
+
+    /// Evaluates a list of nodes, expanding any ...spread into its items
+    pub fn run_spreadable(&mut self, nodes: Vec<Node>) -> Result<Vec<RuntimeValue>, ZephyrError> {
         let mut items: Vec<RuntimeValue> = vec![];
-        for i in expr.items {
-            items.push(self.run(i)?);
+
+        for node in nodes {
+            match node {
+                Node::Spread(spread) => {
+                    let value = self.run(*spread.value)?;
+                    items.extend(self.collect_iter(value, &spread.location)?);
+                }
+                node => items.push(self.run(node)?),
+            }
         }
-        Ok(values::Array::new(items).wrap())
+
+        Ok(items)
+    }
+
+    pub fn run_template_string(&mut self, expr: nodes::TemplateString) -> R {
+        let mut value = String::new();
+        for part in expr.parts {
+            let location = part.location().clone();
+            let part = self.run(part)?;
+            value.push_str(&self.stringify(&part, &location)?);
+        }
+        Ok(values::ZString::new(value).wrap())
     }
 
     pub fn run_object(&mut self, expr: nodes::Object) -> R {
         let mut items: HashMap<String, RuntimeValue> = HashMap::new();
 
-        for (k, v) in expr.items {
-            items.insert(k, self.run(*v.value)?);
+        for item in expr.items {
+            match item {
+                ObjectItem::Property(k, v) => {
+                    items.insert(k, self.run(*v.value)?);
+                }
+                // Objects give their properties, anything else is keyed by index
+                ObjectItem::Spread(spread) => match self.run(*spread.value)? {
+                    RuntimeValue::Object(obj) => {
+                        items.extend(obj.items.borrow().clone());
+                    }
+                    value => {
+                        let values = value.iter().map_err(|err| ZephyrError {
+                            location: Some(spread.location),
+                            ..err
+                        })?;
+                        items.extend(
+                            values
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, v)| (i.to_string(), v)),
+                        );
+                    }
+                },
+            }
         }
 
         Ok(values::Object::new(items).wrap())
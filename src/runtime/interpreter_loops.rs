@@ -1,62 +1,91 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::time::Instant;
 
-use crate::{errors::ErrorCode, parser::nodes};
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    parser::nodes,
+};
 
 use super::{
-    insert_node_timing,
     scope::{Scope, Variable},
-    time_this,
     values::{self, RuntimeValueUtils},
     Interpreter, R,
 };
 
 impl Interpreter {
     pub fn run_while(&mut self, expr: nodes::WhileLoop) -> R {
+        let mut broken = false;
+
         while self.run(*expr.test.clone())?.is_truthy() {
-            match self.run(*expr.body.clone()) {
-                Ok(_) => (),
-                Err(err) => match err.code {
-                    ErrorCode::Break => break,
-                    ErrorCode::Continue => continue,
-                    _ => return Err(err),
-                },
+            if let Err(err) = self.run(*expr.body.clone()) {
+                if Interpreter::loop_interrupt(err, &expr.label)? {
+                    broken = true;
+                    break;
+                }
             }
         }
 
-        Ok(values::Null::new().wrap())
+        self.run_loop_else(broken, expr.on_break)
     }
 
     pub fn run_for(&mut self, expr: nodes::For) -> R {
-        let values = self.run(*expr.iterator)?.iter()?;
-
-        for (i, v) in values.iter().enumerate() {
-            let mut scope: Scope;
-            time_this!("Mini:ForCreateScope".to_string(), {
-                scope = Scope::new_from_parent(self.scope.clone());
-                scope.insert(
-                    expr.index_symbol.value.clone(),
-                    Variable::from(values::Number::new(i as f64).wrap()),
-                    Some(expr.index_symbol.location.clone()),
-                )?;
-
-                if let Some(ref x) = expr.value_symbol {
-                    scope.insert(
-                        x.value.clone(),
-                        Variable::from(v.clone()),
-                        Some(x.location.clone()),
-                    )?;
+        let location = expr.iterator.location().clone();
+        let value = self.run(*expr.iterator)?;
+        let mut values = self.make_iter(value, &location)?;
+        let mut broken = false;
+        let mut i = 0;
+
+        while let Some(v) = self.iter_next(&mut values, &location)? {
+            let mut scope = Scope::new_from_parent(self.scope.clone());
+            scope.insert(
+                expr.index_symbol.value.clone(),
+                Variable::from(values::Number::new(i as f64).wrap()),
+                Some(expr.index_symbol.location.clone()),
+            )?;
+
+            if let Some(ref x) = expr.value_symbol {
+                scope.insert(x.value.clone(), Variable::from(v), Some(x.location.clone()))?;
+            }
+
+            let old_scope = self.swap_scope(Rc::from(RefCell::from(scope)));
+            let result = self.run(*expr.block.clone());
+            self.swap_scope(old_scope);
+
+            if let Err(err) = result {
+                if Interpreter::loop_interrupt(err, &expr.label)? {
+                    broken = true;
+                    break;
                 }
-            });
+            }
+
+            i += 1;
+        }
+
+        self.run_loop_else(broken, expr.on_break)
+    }
+
+    /// Checks if an error is a break / continue meant for the loop with the label,
+    /// giving back whether it was a break. Any other error is given back as the error
+    pub fn loop_interrupt(
+        err: ZephyrError,
+        label: &Option<nodes::Symbol>,
+    ) -> Result<bool, ZephyrError> {
+        let is_for_this = |x: &Option<String>| match x {
+            Some(x) => label.as_ref().is_some_and(|label| label.value == *x),
+            None => true,
+        };
 
-            time_this!("Mini:ForRun".to_string(), {
-                let old_scope = self.swap_scope(Rc::from(RefCell::from(scope)));
-                self.run(*expr.block.clone())?;
-                self.swap_scope(old_scope);
-            });
+        match err.code {
+            ErrorCode::Break(ref x) if is_for_this(x) => Ok(true),
+            ErrorCode::Continue(ref x) if is_for_this(x) => Ok(false),
+            _ => Err(err),
         }
+    }
 
-        Ok(values::Null::new().wrap())
+    pub fn run_loop_else(&mut self, broken: bool, on_break: Option<Box<nodes::Node>>) -> R {
+        match on_break {
+            Some(block) if broken => self.run(*block),
+            _ => Ok(values::Null::new().wrap()),
+        }
     }
 }
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
+    lexer::tokens::Location,
     parser::nodes::{self, Node},
 };
 
@@ -12,7 +13,52 @@ use super::{
 
 impl Interpreter {
     pub fn run_member(&mut self, expr: nodes::Member, set: Option<RuntimeValue>) -> R {
-        let left = self.run(*expr.left.clone())?;
+        match self.run_chain_left(*expr.left.clone(), expr.optional)? {
+            Some(left) => self.member_of(left, expr, set),
+            None => Ok(values::Null::new().wrap()),
+        }
+    }
+
+    /// Evaluates the left of a member or call, giving None if a ?. in the chain hit null
+    /// so the rest of the chain is skipped
+    pub fn run_chain_left(
+        &mut self,
+        left: Node,
+        optional: bool,
+    ) -> Result<Option<RuntimeValue>, ZephyrError> {
+        let location = left.location().clone();
+        let value = match left {
+            Node::Member(expr) => match self.run_chain_left(*expr.left.clone(), expr.optional)? {
+                Some(inner) => self.member_of(inner, expr, None),
+                None => return Ok(None),
+            },
+            Node::Call(expr) => match self.run_chain_left(*expr.left.clone(), expr.optional)? {
+                Some(inner) => self.call_of(inner, expr),
+                None => return Ok(None),
+            },
+            _ => self.run(left),
+        }
+        .map_err(|mut err| {
+            err.location.get_or_insert(location);
+            err
+        })?;
+
+        if optional && matches!(value, RuntimeValue::Null(_)) {
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
+    pub fn member_of(
+        &mut self,
+        left: RuntimeValue,
+        expr: nodes::Member,
+        set: Option<RuntimeValue>,
+    ) -> R {
+        if set.is_some() {
+            left.check_frozen(&expr.location)?;
+        }
 
         // Check if it is being accessed via x.a
         if !expr.computed {
@@ -30,101 +76,119 @@ impl Interpreter {
             return self.member_check_basic(left.clone(), key, set);
         } else {
             let right = self.run(*expr.right.clone())?;
+            return self.index_of(left, right, set, &expr.location);
+        }
+    }
+
+    /// Gets, or sets, left[right] for an already evaluated right
+    pub fn index_of(
+        &mut self,
+        left: RuntimeValue,
+        right: RuntimeValue,
+        set: Option<RuntimeValue>,
+        location: &Location,
+    ) -> R {
+        if set.is_none() {
+            if let Some(result) =
+                self.call_overload(&left, "__index", vec![right.clone()], location)?
+            {
+                return Ok(result);
+            }
+        }
 
-            return match right {
-                RuntimeValue::ZString(string) => {
-                    self.member_check_basic(left.clone(), string.value, set)
+        match right {
+            RuntimeValue::ZString(string) => {
+                self.member_check_basic(left.clone(), string.value, set)
+            }
+            RuntimeValue::RangeValue(_range) => {
+                let mut range = _range.clone();
+                let iter = left.iter()?;
+
+                if range.start < 0f64 {
+                    range.start = iter.len() as f64 + range.start;
                 }
-                RuntimeValue::RangeValue(_range) => {
-                    let mut range = _range.clone();
-                    let iter = left.iter()?;
 
-                    if range.start < 0f64 {
-                        range.start = iter.len() as f64 + range.start;
-                    }
+                if range.end < 0f64 {
+                    range.end = iter.len() as f64 + range.end;
+                }
 
-                    if range.end < 0f64 {
-                        range.end = iter.len() as f64 + range.end;
-                    }
+                let indexes = range
+                    .iter_f64()?
+                    .iter()
+                    .map(|x| *x as usize)
+                    .collect::<Vec<usize>>();
+                let mut parts: Vec<RuntimeValue> = vec![];
 
-                    let indexes = range
-                        .iter_f64()?
-                        .iter()
-                        .map(|x| *x as usize)
-                        .collect::<Vec<usize>>();
-                    let mut parts: Vec<RuntimeValue> = vec![];
-
-                    for index in indexes {
-                        if let Some(val) = iter.get(index) {
-                            parts.push(val.clone());
-                        } else {
-                            return Err(ZephyrError {
-                                message: "Out of bounds".to_string(),
-                                code: ErrorCode::OutOfBounds,
-                                location: Some(expr.location),
-                            });
-                        }
+                for index in indexes {
+                    if let Some(val) = iter.get(index) {
+                        parts.push(val.clone());
+                    } else {
+                        return Err(ZephyrError {
+                            message: "Out of bounds".to_string(),
+                            code: ErrorCode::OutOfBounds,
+                            location: Some(location.clone()),
+                        });
                     }
-
-                    Ok(match left {
-                        /*RuntimeValue::ZString(_) => values::ZString::new(
-                            parts
-                                .iter()
-                                .map(|z| match z {
-                                    RuntimeValue::ZString(a) => a.value.clone(),
-                                    _ => unreachable!(),
-                                })
-                                .collect::<String>(),
-                        )
-                        .wrap(),*/
-                        _ => values::Array::new(parts).wrap(),
-                    })
                 }
-                RuntimeValue::Number(number) => {
-                    if let Some(set) = set {
-                        match left {
-                            RuntimeValue::Array(ref arr) => {
-                                let mut borrow = arr.items.borrow_mut();
-                                if number.value as usize > borrow.len() {
-                                    return Err(ZephyrError { code: ErrorCode::OutOfBounds, message: format!("Trying to assign at index {} but array is only {} items long", number.value, borrow.len()), location: Some(expr.location) });
-                                } else {
-                                    if number.value as usize == borrow.len() {
-                                        borrow.push(set);
-                                    } else {
-                                        borrow[number.value as usize] = set;
-                                    }
 
-                                    return Ok(values::Null::new().wrap());
+                Ok(match left {
+                    /*RuntimeValue::ZString(_) => values::ZString::new(
+                        parts
+                            .iter()
+                            .map(|z| match z {
+                                RuntimeValue::ZString(a) => a.value.clone(),
+                                _ => unreachable!(),
+                            })
+                            .collect::<String>(),
+                    )
+                    .wrap(),*/
+                    _ => values::Array::new(parts).wrap(),
+                })
+            }
+            RuntimeValue::Number(number) => {
+                if let Some(set) = set {
+                    match left {
+                        RuntimeValue::Array(ref arr) => {
+                            let mut borrow = arr.items.borrow_mut();
+                            if number.value as usize > borrow.len() {
+                                return Err(ZephyrError { code: ErrorCode::OutOfBounds, message: format!("Trying to assign at index {} but array is only {} items long", number.value, borrow.len()), location: Some(location.clone()) });
+                            } else {
+                                if number.value as usize == borrow.len() {
+                                    borrow.push(set);
+                                } else {
+                                    borrow[number.value as usize] = set;
                                 }
-                            }
-                            _ => {
-                                return Err(ZephyrError {
-                                    code: ErrorCode::InvalidOperation,
-                                    message: format!("Cannot assign to a {}", left.type_name()),
-                                    location: Some(expr.location),
-                                })
+
+                                return Ok(values::Null::new().wrap());
                             }
                         }
+                        _ => {
+                            return Err(ZephyrError {
+                                code: ErrorCode::InvalidOperation,
+                                message: format!("Cannot assign to a {}", left.type_name()),
+                                location: Some(location.clone()),
+                            })
+                        }
                     }
+                }
 
-                    let iter = left.iter()?;
+                let iter = left.iter()?;
 
-                    if let Some(val) = iter.get(number.value as usize) {
-                        return Ok(val.clone());
-                    } else {
-                        return Err(ZephyrError {
-                            message: "Out of bounds".to_string(),
-                            code: ErrorCode::OutOfBounds,
-                            location: Some(expr.location),
-                        });
-                    }
+                if let Some(val) = iter.get(number.value as usize) {
+                    return Ok(val.clone());
+                } else {
+                    return Err(ZephyrError {
+                        message: "Out of bounds".to_string(),
+                        code: ErrorCode::OutOfBounds,
+                        location: Some(location.clone()),
+                    });
                 }
-                x => Err(ZephyrError {
-                    message: format!("Cannot access {} via {}", left.type_name(), x.type_name()),
-                    code: ErrorCode::TypeError,
-                    location: Some(expr.location),
-                }),
-            };
+            }
+            x => Err(ZephyrError {
+                message: format!("Cannot access {} via {}", left.type_name(), x.type_name()),
+                code: ErrorCode::TypeError,
+                location: Some(location.clone()),
+            }),
         }
     }
 
@@ -185,6 +249,30 @@ impl Interpreter {
                 } else if let Some(val) = obj.items.borrow().get(&key) {
                     return Ok(val.clone());
                 }
+
+                // Structs also give the methods they inherit, so X.y(instance) works for them
+                let mut proto = value.options().tags.borrow().get("__struct").cloned();
+                while let Some(current) = proto.filter(|x| x.starts_with("struct::")) {
+                    let prototype = self.prototype_store.get(current.clone());
+                    if let Some(method) = prototype.items.borrow().get(&key) {
+                        return Ok(method.clone());
+                    }
+
+                    proto = prototype
+                        .options
+                        .proto
+                        .borrow()
+                        .clone()
+                        .filter(|x| *x != current);
+                }
+            }
+            // Fields of shaped enum variants
+            RuntimeValue::EnumVariant(ref variant) if set.is_none() => {
+                if let RuntimeValue::Object(ref inner) = *variant.inner {
+                    if let Some(val) = inner.items.borrow().get(&key) {
+                        return Ok(val.clone());
+                    }
+                }
             }
             _ => (),
         }
@@ -1,6 +1,6 @@
 use crate::{
     errors::{ErrorCode, ZephyrError},
-    lexer::tokens::{self, Logical, TokenType},
+    lexer::tokens::{self, Comparison, Location, Logical, TokenType},
     parser::nodes::{self, IsType, UnaryType},
 };
 
@@ -14,10 +14,21 @@ impl Interpreter {
         let left = self.run(*expr.left)?;
         let right = self.run(*expr.right)?;
 
+        self.arithmetic(left, right, &expr.t, &expr.location)
+    }
+
+    /// Applies an Additive or Multiplicative operator to two values
+    pub fn arithmetic(
+        &mut self,
+        left: RuntimeValue,
+        right: RuntimeValue,
+        t: &TokenType,
+        location: &Location,
+    ) -> R {
         if let (RuntimeValue::Number(left_number), RuntimeValue::Number(right_number)) =
             (&left, &right)
         {
-            return Ok(values::Number::new(match expr.t {
+            return Ok(values::Number::new(match t {
                 TokenType::Additive(tokens::Additive::Plus) => {
                     left_number.value + right_number.value
                 }
@@ -41,12 +52,25 @@ impl Interpreter {
             .wrap());
         }
 
+        let overload = match t {
+            TokenType::Additive(tokens::Additive::Plus) => "__add",
+            TokenType::Additive(tokens::Additive::Minus) => "__sub",
+            TokenType::Multiplicative(tokens::Multiplicative::Multiply) => "__mul",
+            TokenType::Multiplicative(tokens::Multiplicative::Divide) => "__div",
+            TokenType::Multiplicative(tokens::Multiplicative::Modulo) => "__mod",
+            TokenType::Multiplicative(tokens::Multiplicative::Exponent) => "__pow",
+            _ => unreachable!(),
+        };
+        if let Some(result) = self.call_overload(&left, overload, vec![right.clone()], location)? {
+            return Ok(result);
+        }
+
         let result = match left {
             // string ? *
-            RuntimeValue::ZString(ref left_value) => match expr.t {
+            RuntimeValue::ZString(ref left_value) => match t {
                 // string + *
                 TokenType::Additive(tokens::Additive::Plus) => Some(values::ZString::new(
-                    left_value.value.clone() + &right.to_string(false, false, false)?,
+                    left_value.value.clone() + &self.stringify(&right, location)?,
                 )),
                 _ => None,
             },
@@ -60,10 +84,10 @@ impl Interpreter {
                 message: format!(
                     "Cannot handle {} {:?} {}",
                     left.type_name(),
-                    expr.t,
+                    t,
                     right.type_name()
                 ),
-                location: Some(expr.location),
+                location: Some(location.clone()),
             }),
         }
     }
@@ -72,7 +96,130 @@ impl Interpreter {
         let left = self.run(*expr.left)?;
         let right = self.run(*expr.right)?;
 
-        Ok(values::Boolean::new(left.compare_with(right, expr.t, Some(expr.location))?).wrap())
+        Ok(values::Boolean::new(self.compare(left, right, expr.t, &expr.location)?).wrap())
+    }
+
+    /// Compares two values, using __eq and __lt if the values define them
+    pub fn compare(
+        &mut self,
+        left: RuntimeValue,
+        right: RuntimeValue,
+        t: Comparison,
+        location: &Location,
+    ) -> Result<bool, ZephyrError> {
+        if !matches!(
+            (&left, &right),
+            (RuntimeValue::Number(_), RuntimeValue::Number(_))
+        ) {
+            // Everything is written in terms of a == b and a < b
+            let result = match t {
+                Comparison::Eq | Comparison::Neq => self
+                    .call_overload(&left, "__eq", vec![right.clone()], location)?
+                    .map(|x| x.is_truthy() == matches!(t, Comparison::Eq)),
+                Comparison::Lt => self
+                    .call_overload(&left, "__lt", vec![right.clone()], location)?
+                    .map(|x| x.is_truthy()),
+                Comparison::GtEq => self
+                    .call_overload(&left, "__lt", vec![right.clone()], location)?
+                    .map(|x| !x.is_truthy()),
+                Comparison::Gt => self
+                    .call_overload(&right, "__lt", vec![left.clone()], location)?
+                    .map(|x| x.is_truthy()),
+                Comparison::LtEq => self
+                    .call_overload(&right, "__lt", vec![left.clone()], location)?
+                    .map(|x| !x.is_truthy()),
+            };
+
+            if let Some(result) = result {
+                return Ok(result);
+            }
+        }
+
+        left.compare_with(right, t, Some(location.clone()))
+    }
+
+    /// Finds an operator method such as __add in the value's prototype chain
+    pub fn find_overload(&self, value: &RuntimeValue, key: &str) -> Option<RuntimeValue> {
+        let proto = value.options().proto.borrow().clone()?;
+        self.find_in_proto_chain(&proto, key)
+    }
+
+    /// Calls an operator method with the value as self, giving None if the value does not define it
+    pub fn call_overload(
+        &mut self,
+        value: &RuntimeValue,
+        key: &str,
+        mut args: Vec<RuntimeValue>,
+        location: &Location,
+    ) -> Result<Option<RuntimeValue>, ZephyrError> {
+        let Some(func) = self.find_overload(value, key) else {
+            return Ok(None);
+        };
+
+        args.insert(0, value.clone());
+        Ok(Some(self.call_value(func, args, location.clone())?))
+    }
+
+    /// Converts a value into a string, using __str if the value defines it
+    pub fn stringify(
+        &mut self,
+        value: &RuntimeValue,
+        location: &Location,
+    ) -> Result<String, ZephyrError> {
+        self.stringify_with(value, false, false, location)
+    }
+
+    /// The same as RuntimeValue::to_string, but the items of arrays and objects use __str too
+    pub fn stringify_with(
+        &mut self,
+        value: &RuntimeValue,
+        is_display: bool,
+        color: bool,
+        location: &Location,
+    ) -> Result<String, ZephyrError> {
+        match self.call_overload(value, "__str", vec![], location)? {
+            Some(RuntimeValue::ZString(string)) => return Ok(string.value),
+            Some(other) => {
+                return Err(ZephyrError {
+                    message: format!("__str must give a string, got {}", other.type_name()),
+                    code: ErrorCode::TypeError,
+                    location: Some(location.clone()),
+                })
+            }
+            None => (),
+        }
+
+        match value {
+            RuntimeValue::Array(arr) => {
+                let items = arr.items.borrow().clone();
+                let parts = items
+                    .iter()
+                    .map(|x| self.stringify_with(x, true, color, location))
+                    .collect::<Result<Vec<String>, ZephyrError>>()?;
+
+                Ok(format!("[{}]", parts.join(", ")))
+            }
+            RuntimeValue::Object(obj) => {
+                let items = obj.items.borrow().clone();
+                let parts = items
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok(format!(
+                            "{}: {}",
+                            k,
+                            self.stringify_with(v, true, color, location)?
+                        ))
+                    })
+                    .collect::<Result<Vec<String>, ZephyrError>>()?;
+
+                Ok(format!(
+                    "{}{{{}}}",
+                    if is_display { "." } else { "" },
+                    parts.join(", ")
+                ))
+            }
+            _ => value.to_string(is_display, color, false),
+        }
     }
 
     pub fn run_logical(&mut self, expr: nodes::Logical) -> R {
@@ -88,12 +235,28 @@ impl Interpreter {
         Ok(values::Boolean::new(result).wrap())
     }
 
+    pub fn run_coalesce(&mut self, expr: nodes::Coalesce) -> R {
+        match self.run(*expr.left)? {
+            RuntimeValue::Null(_) => self.run(*expr.right),
+            left => Ok(left),
+        }
+    }
+
     pub fn run_unary(&mut self, expr: nodes::Unary) -> R {
+        if matches!(expr.t, UnaryType::Increment | UnaryType::Decrement) {
+            return self.run_increment(expr);
+        }
+
         let left = self.run(*expr.value)?;
 
         if !expr.is_right {
             match expr.t {
-                UnaryType::LengthOf => Ok(values::Number::new(left.len()? as f64).wrap()),
+                UnaryType::LengthOf => {
+                    match self.call_overload(&left, "__len", vec![], &expr.location)? {
+                        Some(len) => Ok(len),
+                        None => Ok(values::Number::new(left.len()? as f64).wrap()),
+                    }
+                }
                 UnaryType::Not => Ok(values::Boolean::new(!left.is_truthy()).wrap()),
                 UnaryType::Minus => match left {
                     RuntimeValue::Number(n) => Ok(values::Number::new(-n.value).wrap()),
@@ -108,12 +271,43 @@ impl Interpreter {
                 _ => unreachable!(),
             }
         } else {
-            match expr.t {
-                _ => unreachable!(),
-            }
+            unreachable!()
         }
     }
 
+    /// ++x / --x give the new value, x++ / x-- give the old one
+    pub fn run_increment(&mut self, expr: nodes::Unary) -> R {
+        self.update_target(*expr.value, |_, current| {
+            let old = match current {
+                RuntimeValue::Number(n) => n.value,
+                x => {
+                    return Err(ZephyrError {
+                        message: format!(
+                            "Cannot {} a {}",
+                            match expr.t {
+                                UnaryType::Increment => "increment",
+                                _ => "decrement",
+                            },
+                            x.type_name()
+                        ),
+                        code: ErrorCode::TypeError,
+                        location: Some(expr.location.clone()),
+                    })
+                }
+            };
+
+            let new = match expr.t {
+                UnaryType::Increment => old + 1f64,
+                _ => old - 1f64,
+            };
+
+            Ok((
+                values::Number::new(new).wrap(),
+                values::Number::new(if expr.is_right { old } else { new }).wrap(),
+            ))
+        })
+    }
+
     pub fn run_inner_is(&mut self, left: RuntimeValue, right: IsType) -> R {
         Ok(values::Boolean::new(match right {
             nodes::IsType::Basic(_right) => {
@@ -121,7 +315,9 @@ impl Interpreter {
 
                 // Check for __enum_base
                 let right_tags = right.options().tags.borrow();
-                if let Some(enum_id) = right_tags.get("__enum_base").cloned() {
+                if let Some(proto) = right_tags.get("__struct") {
+                    self.extends_proto(&left, proto)
+                } else if let Some(enum_id) = right_tags.get("__enum_base").cloned() {
                     if let RuntimeValue::EnumVariant(e) = left {
                         if e.enum_id == enum_id {
                             true
@@ -140,8 +336,93 @@ impl Interpreter {
         .wrap())
     }
 
+    /// Whether a value has the prototype somewhere in its proto chain
+    pub fn extends_proto(&self, value: &RuntimeValue, proto: &str) -> bool {
+        let mut proto_ref = value.options().proto.borrow().clone();
+
+        while let Some(current) = proto_ref {
+            if current == proto {
+                return true;
+            }
+
+            proto_ref = self
+                .prototype_store
+                .get(current.clone())
+                .options
+                .proto
+                .borrow()
+                .clone()
+                .filter(|x| *x != current);
+        }
+
+        false
+    }
+
     pub fn run_is(&mut self, expr: nodes::Is) -> R {
         let left = self.run(*expr.left)?;
         self.run_inner_is(left, expr.right)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::test::run;
+
+    const VEC: &str = "struct V(x, y) {
+        func __add(self, other) { V(self.x + other.x, self.y + other.y) }
+        func __eq(self, other) { self.x == other.x && self.y == other.y }
+        func __lt(self, other) { self.x < other.x }
+        func __index(self, i) { if i == 0 { self.x } else { self.y } }
+        func __len(self) { 2 }
+        func __call(self, n) { self.x * n }
+        func __str(self) { `V({self.x}, {self.y})` }
+    }";
+
+    #[test]
+    fn overloads() {
+        let cases = [
+            ("let a = V(1, 2); a += V(3, 4); a", "V(4, 6)"),
+            ("V(1, 2) == V(1, 2)", "true"),
+            ("V(1, 2) != V(1, 3)", "true"),
+            (
+                "[V(1, 0) < V(2, 0), V(1, 0) > V(2, 0), V(1, 0) >= V(1, 5)]",
+                "[true, false, true]",
+            ),
+            (
+                "match V(1, 2) { V(0, 0) -> 0, V(1, 2) -> 1, else -> 2 }",
+                "1",
+            ),
+            ("let v = V(5, 6); [v[0], v[1], $v, v(3)]", "[5, 6, 2, 15]"),
+            ("`{V(1, 2)}` + \" \" + V(3, 4)", "V(1, 2) V(3, 4)"),
+        ];
+
+        for (code, expected) in cases {
+            assert_eq!(
+                run(&format!("{} {}", VEC, code), false),
+                expected,
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn nested_str() {
+        assert_eq!(
+            run(&format!("{} `{{[V(1, 2), 3]}}`", VEC), false),
+            "[V(1, 2), 3]"
+        );
+        assert_eq!(
+            run(&format!("{} `{{.{{ a: [V(1, 2)] }}}}`", VEC), false),
+            "{a: [V(1, 2)]}"
+        );
+    }
+
+    #[test]
+    fn str_must_give_string() {
+        assert_eq!(
+            run("struct S { func __str(self) { 1 } } `{S()}`", false),
+            "error: __str must give a string, got number"
+        );
+    }
+}
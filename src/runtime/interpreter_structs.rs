@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    lexer::tokens::Location,
+    parser::nodes,
+};
+
+use super::{
+    scope::Variable,
+    values::{self, Object, RuntimeValue, RuntimeValueUtils},
+    Interpreter, R,
+};
+
+impl Interpreter {
+    pub fn run_struct(&mut self, expr: nodes::Struct) -> R {
+        let parent = match expr.extends {
+            Some(extends) => {
+                let location = extends.location().clone();
+                let value = self.run(*extends)?;
+                let parent = value.options().tags.borrow().get("__struct").cloned();
+
+                match parent {
+                    Some(parent) => parent,
+                    None => {
+                        return Err(ZephyrError {
+                            message: format!("Can only extend a struct, got {}", value.type_name()),
+                            code: ErrorCode::TypeError,
+                            location: Some(location),
+                        })
+                    }
+                }
+            }
+            None => "object".to_string(),
+        };
+
+        // Instances get this as their prototype, so methods are found via the proto chain
+        let proto = format!("struct::{}::{}", expr.name.value, uuid::Uuid::new_v4());
+        let obj = Object::new_empty();
+        obj.options.proto.borrow_mut().replace(parent);
+
+        for method in expr.methods {
+            let name = method.name.clone().unwrap().value;
+            let func = self.run_make_function(method)?;
+            obj.items.borrow_mut().insert(name, func);
+        }
+
+        self.prototype_store.set(proto.clone(), obj.clone());
+
+        // The struct itself shares its items with the prototype, so Point.method(x) can be used
+        let value = Object::new_from_rc(obj.items.clone()).wrap();
+        {
+            let mut tags = value.options().tags.borrow_mut();
+            tags.insert("__struct".to_string(), proto);
+            tags.insert("__struct_name".to_string(), expr.name.value.clone());
+            if let Some(fields) = expr.fields {
+                tags.insert(
+                    "__struct_fields".to_string(),
+                    fields
+                        .into_iter()
+                        .map(|x| x.value)
+                        .collect::<Vec<String>>()
+                        .join(","),
+                );
+            }
+        }
+        value.freeze(false);
+
+        self.scope.borrow_mut().insert(
+            expr.name.value,
+            Variable {
+                is_const: true,
+                value,
+            },
+            Some(expr.name.location.clone()),
+        )?;
+
+        Ok(values::Null::new().wrap())
+    }
+
+    /// Creates an instance of a struct, running its init method if it has one
+    pub fn construct(
+        &mut self,
+        left: &RuntimeValue,
+        proto: String,
+        args: Vec<RuntimeValue>,
+        named: HashMap<String, RuntimeValue>,
+        location: Location,
+    ) -> R {
+        let tags = left.options().tags.borrow().clone();
+        let name = tags.get("__struct_name").cloned().unwrap_or_default();
+        let fields = tags
+            .get("__struct_fields")
+            .map(|x| {
+                x.split(',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+
+        if let Some(init) = self.find_in_proto_chain(&proto, "init") {
+            let instance = Object::new(
+                fields
+                    .into_iter()
+                    .map(|x| (x, values::Null::new().wrap()))
+                    .collect::<HashMap<String, RuntimeValue>>(),
+            )
+            .wrap()
+            .set_proto(proto);
+
+            let mut init_args = vec![instance.clone()];
+            init_args.extend(args);
+            self.call_value_with_named(init, init_args, named, location)?;
+
+            return Ok(instance);
+        }
+
+        Ok(self
+            .map_fields(&fields, args, named, &name, &location)?
+            .set_proto(proto))
+    }
+
+    /// Looks for a key in a prototype and the prototypes it extends
+    pub fn find_in_proto_chain(&self, proto: &str, key: &str) -> Option<RuntimeValue> {
+        let mut proto_ref = Some(proto.to_string());
+
+        while let Some(proto) = proto_ref {
+            let prototype = self.prototype_store.get(proto.clone());
+            if let Some(value) = prototype.items.borrow().get(key) {
+                return Some(value.clone());
+            }
+
+            proto_ref = prototype
+                .options
+                .proto
+                .borrow()
+                .clone()
+                .filter(|x| *x != proto);
+        }
+
+        None
+    }
+}
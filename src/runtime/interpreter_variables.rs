@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
+    lexer::tokens::Location,
     parser::nodes::{self, DeclareType, Node},
 };
 
@@ -19,65 +20,157 @@ impl Interpreter {
             values::Null::new().wrap()
         };
 
-        match expr.assignee {
+        self.declare_pattern(&expr.assignee, value.clone(), expr.is_const, &expr.location)?;
+
+        Ok(value)
+    }
+
+    /// Defines the variables of a declaration pattern, recursing into array and object patterns
+    pub fn declare_pattern(
+        &mut self,
+        pattern: &DeclareType,
+        value: RuntimeValue,
+        is_const: bool,
+        location: &Location,
+    ) -> Result<(), ZephyrError> {
+        match pattern {
             DeclareType::Symbol(s) => self.scope.borrow_mut().insert(
-                s.value,
-                Variable {
-                    is_const: expr.is_const,
-                    value: value.clone(),
-                },
+                s.value.clone(),
+                Variable { is_const, value },
                 Some(s.location.clone()),
             )?,
-            DeclareType::Array(a) => match value {
-                RuntimeValue::Array(ref arr) => {
-                    for (i, v) in a.iter().enumerate() {
-                        if let Some(val) = arr.items.borrow().get(i) {
-                            self.scope.borrow_mut().insert(
-                                v.value.clone(),
-                                Variable {
-                                    is_const: expr.is_const,
-                                    value: val.clone(),
-                                },
-                                Some(v.location.clone()),
-                            )?;
-                        } else {
-                            return Err(ZephyrError {
-                                message: "Out of bounds".to_string(),
-                                code: ErrorCode::OutOfBounds,
-                                location: Some(v.location.clone()),
-                            });
-                        }
+            DeclareType::Array(a) => {
+                // Only as many values as are needed are taken, so infinite iterators work
+                let type_name = value.type_name().to_string();
+                let mut iter = self
+                    .make_iter(value, location)
+                    .map_err(|err| match err.code {
+                        ErrorCode::CannotIterate => ZephyrError {
+                            message: format!(
+                                "Cannot assign to a array declaration with a {}",
+                                type_name
+                            ),
+                            code: ErrorCode::TypeError,
+                            location: Some(location.clone()),
+                        },
+                        _ => err,
+                    })?;
+
+                for v in a {
+                    if let Some(val) = self.iter_next(&mut iter, location)? {
+                        self.declare_pattern(v, val, is_const, location)?;
+                    } else {
+                        return Err(ZephyrError {
+                            message: "Out of bounds".to_string(),
+                            code: ErrorCode::OutOfBounds,
+                            location: Some(location.clone()),
+                        });
                     }
                 }
-                x => {
-                    return Err(ZephyrError {
-                        message: format!(
-                            "Cannot assign to a array declaration with a {}",
-                            x.type_name()
-                        ),
-                        code: ErrorCode::TypeError,
-                        location: Some(expr.location.clone()),
-                    })
+            }
+            DeclareType::Object(parts) => {
+                for part in parts {
+                    let item = match self.member_check_basic(
+                        value.clone(),
+                        part.key.value.clone(),
+                        None,
+                    ) {
+                        Ok(ok) => ok,
+                        Err(err) => match (&err.code, &part.default) {
+                            (ErrorCode::InvalidProperty, Some(default)) => {
+                                self.run(*default.clone())?
+                            }
+                            _ => {
+                                return Err(ZephyrError {
+                                    location: Some(part.key.location.clone()),
+                                    ..err
+                                })
+                            }
+                        },
+                    };
+
+                    self.declare_pattern(&part.assignee, item, is_const, location)?;
                 }
-            },
-            _ => panic!(),
+            }
         }
 
-        Ok(value.clone())
+        Ok(())
     }
 
     pub fn run_assign(&mut self, expr: nodes::Assign) -> R {
         let value = self.run(*expr.value)?;
 
-        match *expr.assignee {
+        // a += b is a = a + b, with a only evaluated once
+        if let Some(operator) = expr.operator {
+            return self.update_target(*expr.assignee, |interpreter, current| {
+                let value = interpreter.arithmetic(current, value, &operator, &expr.location)?;
+                Ok((value.clone(), value))
+            });
+        }
+
+        self.assign_to(*expr.assignee, value)
+    }
+
+    /// Reads a symbol or member, then stores what the update gives back, evaluating the member's
+    /// object and key only once
+    ///
+    /// The update gives the value to store and the value to give back
+    pub fn update_target(
+        &mut self,
+        target: Node,
+        update: impl FnOnce(
+            &mut Self,
+            RuntimeValue,
+        ) -> Result<(RuntimeValue, RuntimeValue), ZephyrError>,
+    ) -> R {
+        let Node::Member(member) = target else {
+            let current = self.run(target.clone())?;
+            let (value, result) = update(self, current)?;
+            self.assign_to(target, value)?;
+            return Ok(result);
+        };
+
+        let Some(left) = self.run_chain_left(*member.left.clone(), member.optional)? else {
+            return Ok(values::Null::new().wrap());
+        };
+
+        if member.computed {
+            let key = self.run(*member.right)?;
+            let current = self.index_of(left.clone(), key.clone(), None, &member.location)?;
+            let (value, result) = update(self, current)?;
+            left.check_frozen(&member.location)?;
+            self.index_of(left, key, Some(value), &member.location)?;
+            Ok(result)
+        } else {
+            let Node::Symbol(key) = *member.right else {
+                return Err(ZephyrError {
+                    message: "Expected a symbol".to_string(),
+                    code: ErrorCode::TypeError,
+                    location: Some(member.location),
+                });
+            };
+            let current = self.member_check_basic(left.clone(), key.value.clone(), None)?;
+            let (value, result) = update(self, current)?;
+            left.check_frozen(&member.location)?;
+            self.member_check_basic(left, key.value, Some(value))?;
+            Ok(result)
+        }
+    }
+
+    /// Stores a value in a symbol or member, giving back the value
+    pub fn assign_to(&mut self, assignee: Node, value: RuntimeValue) -> R {
+        match assignee {
             Node::Symbol(ref symbol) => {
-                self.scope.borrow_mut().modify(
-                    symbol.value.clone(),
+                self.scope.borrow_mut().modify_at(
+                    &symbol.value,
+                    symbol.address,
                     value.clone(),
-                    Some(expr.assignee.location().clone()),
+                    Some(symbol.location.clone()),
                 )?;
             }
-            Node::Member(member) => return self.run_member(member, Some(value)),
+            Node::Member(member) => {
+                self.run_member(member, Some(value.clone()))?;
+            }
             x => {
                 return Err(ZephyrError {
                     code: ErrorCode::InvalidOperation,
@@ -96,20 +189,62 @@ impl Interpreter {
         let proto = uuid::Uuid::new_v4().to_string();
         let obj = Object::new_empty();
         obj.options.proto.borrow_mut().replace("enum".to_string());
+
+        for method in expr.methods {
+            let name = method.name.clone().unwrap().value;
+            let func = self.run_make_function(method)?;
+            obj.items.borrow_mut().insert(name, func);
+        }
+
+        // Declaration order, used for listing variants and checking matches
+        let names = expr
+            .values
+            .iter()
+            .map(|x| x.name.value.clone())
+            .collect::<Vec<String>>()
+            .join(",");
+        obj.options
+            .tags
+            .borrow_mut()
+            .insert("__enum_variants".to_string(), names.clone());
         self.prototype_store.set(proto.clone(), obj);
 
-        for (key, value) in expr.values {
-            let val = values::ZString::new("".to_string());
-            val.options.proto.borrow_mut().replace(proto.clone());
-            val.options
-                .tags
-                .borrow_mut()
-                .insert("__enum_base".to_string(), value.clone());
-            items.insert(key.value, val.wrap());
+        for item in expr.values {
+            // The variant itself is a payloadless value which can be called to give it a payload
+            let val = values::EnumVariant::new(values::Null::new().wrap(), item.id.clone())
+                .wrap()
+                .set_proto(proto.clone());
+            let mut tags = val.options().tags.borrow_mut();
+            tags.insert("__enum_base".to_string(), item.id);
+            if let Some(fields) = item.fields {
+                tags.insert(
+                    "__enum_fields".to_string(),
+                    fields
+                        .into_iter()
+                        .map(|x| x.value)
+                        .collect::<Vec<String>>()
+                        .join(","),
+                );
+            }
+            drop(tags);
+
+            if items.contains_key(&item.name.value) {
+                return Err(ZephyrError {
+                    message: format!("Enum item {} is already defined", item.name.value),
+                    code: ErrorCode::AlreadyDefined,
+                    location: Some(item.name.location),
+                });
+            }
+            items.insert(item.name.value, val);
         }
 
         let obj = values::Object::new(items).wrap();
         obj.options().proto.borrow_mut().replace(proto.clone());
+        obj.options()
+            .tags
+            .borrow_mut()
+            .insert("__enum_variants".to_string(), names);
+        obj.freeze(false);
 
         self.scope.borrow_mut().insert(
             expr.name.value,
@@ -0,0 +1,440 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    compiler::{
+        bytecode::{Chunk, Op},
+        Compiler,
+    },
+    errors::{ErrorCode, TailCall, ZephyrError},
+    parser::nodes,
+};
+
+use super::{
+    gc,
+    interpreter_iterators::ValueIter,
+    scope::{Scope, ScopeInnerType, Variable},
+    values::{self, RuntimeValue, RuntimeValueDetails, RuntimeValueUtils},
+    Interpreter, R,
+};
+
+/// A loop which is currently running, so break and continue know what to restore
+struct LoopRecord {
+    info: usize,
+    stack: usize,
+    scopes: usize,
+    iters: usize,
+}
+
+/// The state of a single run of a chunk
+#[derive(Default)]
+struct Frame {
+    stack: Vec<RuntimeValue>,
+    /// The scopes which were swapped out by PushScope
+    scopes: Vec<ScopeInnerType>,
+    iters: Vec<(ValueIter, usize)>,
+    loops: Vec<LoopRecord>,
+}
+
+impl Frame {
+    fn pop(&mut self) -> RuntimeValue {
+        self.stack.pop().expect("The VM's stack is empty")
+    }
+
+    fn peek(&self) -> &RuntimeValue {
+        self.stack.last().expect("The VM's stack is empty")
+    }
+
+    /// Pops what Call / TailCall pushed, being what is called, its arguments and its named arguments
+    fn pop_call(
+        &mut self,
+        count: usize,
+        names: &[String],
+    ) -> (
+        RuntimeValue,
+        Vec<RuntimeValue>,
+        HashMap<String, RuntimeValue>,
+    ) {
+        let named_values = self.stack.split_off(self.stack.len() - names.len());
+        let args = self.stack.split_off(self.stack.len() - count);
+        let left = self.pop();
+
+        let named = names
+            .iter()
+            .cloned()
+            .zip(named_values)
+            .collect::<HashMap<String, RuntimeValue>>();
+        (left, args, named)
+    }
+}
+
+impl Interpreter {
+    /// Compiles the node into bytecode and runs it with the VM
+    pub fn run_compiled(&mut self, node: &nodes::Node) -> R {
+        let chunk = Compiler::compile(node);
+        self.run_chunk(&chunk)
+    }
+
+    /// Runs compiled bytecode, giving the same result as running the nodes it was compiled from
+    pub fn run_chunk(&mut self, chunk: &Chunk) -> R {
+        let mut frame = Frame::default();
+        let mut ip = 0;
+
+        while let Some(op) = chunk.ops.get(ip) {
+            ip += 1;
+
+            match self.run_op(op, chunk, &mut frame, &mut ip) {
+                Ok(None) => (),
+                Ok(Some(value)) => {
+                    self.restore_scope(&mut frame, 0);
+                    return Ok(value);
+                }
+                Err(mut err) => {
+                    err.location.get_or_insert(chunk.locations[ip - 1].clone());
+
+                    match self.unwind_chunk(chunk, &mut frame, err) {
+                        Ok(to) => ip = to,
+                        Err(err) => {
+                            self.restore_scope(&mut frame, 0);
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(frame
+            .stack
+            .pop()
+            .unwrap_or_else(|| values::Null::new().wrap()))
+    }
+
+    /// Runs a single op, giving Some when the chunk returned
+    fn run_op(
+        &mut self,
+        op: &Op,
+        chunk: &Chunk,
+        frame: &mut Frame,
+        ip: &mut usize,
+    ) -> Result<Option<RuntimeValue>, ZephyrError> {
+        let location = &chunk.locations[*ip - 1];
+
+        match op {
+            // ----- values -----
+            Op::Number(value) => frame.stack.push(values::Number::new(*value).wrap()),
+            Op::String(value) => frame
+                .stack
+                .push(values::ZString::new(value.to_string()).wrap()),
+            Op::Boolean(value) => frame.stack.push(values::Boolean::new(*value).wrap()),
+            Op::Null => frame.stack.push(values::Null::new().wrap()),
+            Op::Array(count) => {
+                let items = frame.stack.split_off(frame.stack.len() - count);
+                frame.stack.push(values::Array::new(items).wrap());
+            }
+            Op::MakeFunction(template) => {
+                gc::track_scope(&self.scope);
+                frame.stack.push(
+                    values::Function {
+                        options: RuntimeValueDetails::default(),
+                        inner: values::FunctionInner {
+                            body: template.body.clone(),
+                            name: template.name.clone(),
+                            scope: self.scope.clone(),
+                            arguments: template.args.clone(),
+                            is_generator: template.is_generator,
+                            is_async: template.is_async,
+                            chunk: template.chunk.clone(),
+                        },
+                    }
+                    .wrap(),
+                )
+            }
+
+            // ----- stack -----
+            Op::Pop => {
+                frame.pop();
+            }
+            Op::Nip => {
+                let top = frame.pop();
+                frame.pop();
+                frame.stack.push(top);
+            }
+            Op::Swap => {
+                let len = frame.stack.len();
+                frame.stack.swap(len - 1, len - 2);
+            }
+
+            // ----- variables -----
+            Op::Load(name, address) => {
+                let value =
+                    self.scope
+                        .borrow()
+                        .lookup_at(name, *address, Some(location.clone()))?;
+                frame.stack.push(match value {
+                    RuntimeValue::Export(r) => r.inner()?,
+                    v => v,
+                });
+            }
+            Op::Declare(name, is_const) => {
+                let value = frame.peek().clone();
+                self.scope.borrow_mut().insert(
+                    name.to_string(),
+                    Variable {
+                        is_const: *is_const,
+                        value,
+                    },
+                    Some(location.clone()),
+                )?;
+            }
+            Op::DeclarePattern(pattern, is_const) => {
+                let value = frame.peek().clone();
+                self.declare_pattern(pattern, value, *is_const, location)?;
+            }
+            Op::Store(name, address) => {
+                let value = frame.peek().clone();
+                self.scope
+                    .borrow_mut()
+                    .modify_at(name, *address, value, Some(location.clone()))?;
+            }
+
+            // ----- operators -----
+            Op::Arithmetic(t) => {
+                let right = frame.pop();
+                let left = frame.pop();
+                let value = self.arithmetic(left, right, t, location)?;
+                frame.stack.push(value);
+            }
+            Op::Compare(t) => {
+                let right = frame.pop();
+                let left = frame.pop();
+                let value = self.compare(left, right, t.clone(), location)?;
+                frame.stack.push(values::Boolean::new(value).wrap());
+            }
+            Op::Not => {
+                let value = frame.pop();
+                frame
+                    .stack
+                    .push(values::Boolean::new(!value.is_truthy()).wrap());
+            }
+            Op::Negate => match frame.pop() {
+                RuntimeValue::Number(n) => frame.stack.push(values::Number::new(-n.value).wrap()),
+                x => {
+                    return Err(ZephyrError {
+                        message: format!("Cannot make {} negative", x.type_name()),
+                        code: ErrorCode::TypeError,
+                        location: Some(location.clone()),
+                    })
+                }
+            },
+            Op::LengthOf => {
+                let value = frame.pop();
+                let len = match self.call_overload(&value, "__len", vec![], location)? {
+                    Some(len) => len,
+                    None => values::Number::new(value.len()? as f64).wrap(),
+                };
+                frame.stack.push(len);
+            }
+            Op::Truthy => {
+                let value = frame.pop();
+                frame
+                    .stack
+                    .push(values::Boolean::new(value.is_truthy()).wrap());
+            }
+
+            // ----- members & calls -----
+            Op::Member(key) => {
+                let left = frame.pop();
+                let value = self.member_check_basic(left, key.to_string(), None)?;
+                frame.stack.push(value);
+            }
+            Op::Index => {
+                let right = frame.pop();
+                let left = frame.pop();
+                let value = self.index_of(left, right, None, location)?;
+                frame.stack.push(value);
+            }
+            Op::Call(count, names) => {
+                let (left, args, named) = frame.pop_call(*count, names);
+                let value = self.call_value_with_named(left, args, named, location.clone())?;
+                frame.stack.push(value);
+            }
+            Op::TailCall(count, names) => {
+                let (function, args, named) = frame.pop_call(*count, names);
+                return Err(ZephyrError {
+                    message: "Cannot return here".to_string(),
+                    code: ErrorCode::TailCall(Box::new(TailCall {
+                        function,
+                        args,
+                        named,
+                    })),
+                    location: Some(location.clone()),
+                });
+            }
+
+            // ----- control flow -----
+            Op::Jump(to) => *ip = *to,
+            Op::JumpIfFalse(to) => {
+                if !frame.pop().is_truthy() {
+                    *ip = *to;
+                }
+            }
+            Op::JumpIfTrue(to) => {
+                if frame.pop().is_truthy() {
+                    *ip = *to;
+                }
+            }
+            Op::JumpIfNotNull(to) => {
+                if matches!(frame.peek(), RuntimeValue::Null(_)) {
+                    frame.pop();
+                } else {
+                    *ip = *to;
+                }
+            }
+            Op::PushScope => {
+                let scope = Scope::new_from_parent(self.scope.clone());
+                frame
+                    .scopes
+                    .push(self.swap_scope(Rc::from(RefCell::from(scope))));
+            }
+            Op::PopScope => {
+                let depth = frame.scopes.len() - 1;
+                self.restore_scope(frame, depth);
+            }
+            Op::Return => {
+                let value = frame.pop();
+                if chunk.is_function {
+                    return Ok(Some(value));
+                }
+
+                return Err(ZephyrError {
+                    message: "Cannot return here".to_string(),
+                    code: ErrorCode::Return(Some(value)),
+                    location: Some(location.clone()),
+                });
+            }
+
+            // ----- loops -----
+            Op::LoopEnter(info) => frame.loops.push(LoopRecord {
+                info: *info,
+                stack: frame.stack.len(),
+                scopes: frame.scopes.len(),
+                iters: frame.iters.len(),
+            }),
+            Op::LoopExit => {
+                frame.loops.pop();
+            }
+            Op::IterStart => {
+                let value = frame.pop();
+                let iter = self.make_iter(value, location)?;
+                frame.iters.push((iter, 0));
+            }
+            Op::ForNext(symbols, done) => {
+                let (iter, index) = frame.iters.last_mut().expect("No iterator to continue");
+                let Some(value) = self.iter_next(iter, location)? else {
+                    *ip = *done;
+                    return Ok(None);
+                };
+
+                let (index_symbol, value_symbol) = &**symbols;
+                let mut scope = Scope::new_from_parent(self.scope.clone());
+                scope.insert(
+                    index_symbol.value.clone(),
+                    Variable::from(values::Number::new(*index as f64).wrap()),
+                    Some(index_symbol.location.clone()),
+                )?;
+                if let Some(symbol) = value_symbol {
+                    scope.insert(
+                        symbol.value.clone(),
+                        Variable::from(value),
+                        Some(symbol.location.clone()),
+                    )?;
+                }
+                *index += 1;
+
+                frame
+                    .scopes
+                    .push(self.swap_scope(Rc::from(RefCell::from(scope))));
+            }
+            Op::IterEnd => {
+                frame.iters.pop();
+            }
+
+            // ----- match -----
+            Op::MatchPattern(pattern, next) => {
+                let mut bindings: Vec<(nodes::Symbol, RuntimeValue)> = vec![];
+                let subject = frame.peek().clone();
+                if !self.match_pattern(pattern, &subject, &mut bindings)? {
+                    *ip = *next;
+                    return Ok(None);
+                }
+
+                let mut scope = Scope::new_from_parent(self.scope.clone());
+                for (symbol, value) in bindings {
+                    scope.insert(symbol.value, Variable::from(value), Some(symbol.location))?;
+                }
+                frame
+                    .scopes
+                    .push(self.swap_scope(Rc::from(RefCell::from(scope))));
+            }
+
+            // ----- other -----
+            Op::Debug => {
+                let value = frame.pop();
+                self.debug_value(&value, location)?;
+                frame.stack.push(values::Null::new().wrap());
+            }
+            Op::Await => {
+                let value = frame.pop();
+                let value = self.await_value(value, location)?;
+                frame.stack.push(value);
+            }
+            Op::Eval(node) => {
+                let value = self.run((**node).clone())?;
+                frame.stack.push(value);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Swaps back to the scope which was current when there were depth scopes pushed
+    fn restore_scope(&mut self, frame: &mut Frame, depth: usize) {
+        if frame.scopes.len() > depth {
+            self.scope = frame.scopes[depth].clone();
+            frame.scopes.truncate(depth);
+        }
+    }
+
+    /// Gives a break / continue to the loop it is for, giving where to continue from
+    /// Any other error, or one which is not for a loop in this chunk, is given back
+    fn unwind_chunk(
+        &mut self,
+        chunk: &Chunk,
+        frame: &mut Frame,
+        mut err: ZephyrError,
+    ) -> Result<usize, ZephyrError> {
+        if !matches!(err.code, ErrorCode::Break(_) | ErrorCode::Continue(_)) {
+            return Err(err);
+        }
+
+        while let Some(record) = frame.loops.pop() {
+            let info = &chunk.loops[record.info];
+
+            match Interpreter::loop_interrupt(err, &info.label) {
+                Ok(broken) => {
+                    frame.stack.truncate(record.stack);
+                    frame.iters.truncate(record.iters);
+                    self.restore_scope(frame, record.scopes);
+                    frame.loops.push(record);
+
+                    return Ok(match broken {
+                        true => info.break_to,
+                        false => info.continue_to,
+                    });
+                }
+                Err(inner) => err = inner,
+            }
+        }
+
+        Err(err)
+    }
+}
@@ -1,13 +1,8 @@
 use std::{
-    cell::RefCell,
-    cmp::Reverse,
+    cell::{Cell, RefCell},
     collections::HashMap,
     rc::Rc,
-    sync::{
-        mpsc::{channel, Receiver, Sender, TryRecvError},
-        Arc, LazyLock, Mutex,
-    },
-    time::Instant,
+    sync::mpsc::{channel, Receiver, Sender},
 };
 use uuid::Uuid;
 use scope::{Scope, ScopeInnerType, Variable};
@@ -15,27 +10,31 @@ use values::{Null, RuntimeValue, RuntimeValueUtils};
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
-    lexer::{
-        lexer::lex,
-        tokens::{Location, NO_LOCATION},
-    },
+    lexer::{lexer::lex, tokens::Location},
     parser::{
         nodes::{self, InterruptType, Node},
         Parser,
     },
 };
 
+pub mod gc;
+pub mod interpreter_async;
 pub mod interpreter_conditionals;
 pub mod interpreter_errors;
 pub mod interpreter_functions;
+pub mod interpreter_generators;
 pub mod interpreter_helper;
 pub mod interpreter_imports;
+pub mod interpreter_iterators;
 pub mod interpreter_literals;
 pub mod interpreter_loops;
 pub mod interpreter_objects;
 pub mod interpreter_operators;
+pub mod interpreter_structs;
 pub mod interpreter_variables;
+pub mod interpreter_vm;
 pub mod native;
+pub mod profiler;
 pub mod prototype_store;
 pub mod scope;
 pub mod values;
@@ -43,6 +42,13 @@ pub mod zephyr_mspc;
 
 type R = Result<RuntimeValue, ZephyrError>;
 
+/// How many Zephyr calls can be inside of each other before a StackOverflow error, unless
+/// --max-call-depth is given
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+/// The Rust stack a single Zephyr call can take, the interpreter's thread is given enough for the
+/// maximum call depth
+pub const STACK_PER_CALL: usize = 512 * 1024;
+
 macro_rules! include_lib {
     ($what:expr) => {
         (include_str!($what), $what)
@@ -53,6 +59,7 @@ pub struct Module {
     pub exports: HashMap<String, Option<RuntimeValue>>,
     pub scope: ScopeInnerType,
     pub wanted: Vec<(String, Location)>,
+    pub loaded: bool,
 }
 
 #[derive(Clone)]
@@ -61,26 +68,26 @@ pub struct Interpreter {
     pub global_scope: ScopeInnerType,
     pub module_cache: HashMap<String, Rc<RefCell<Module>>>,
     pub mspc: Option<zephyr_mspc::MspcChannel>,
-    pub thread_count: usize,
+    /// Receives the messages from threads, which are handled by the event loop
+    pub receiver: Option<Rc<Receiver<zephyr_mspc::MspcSendType>>>,
+    pub thread_count: Rc<Cell<usize>>,
+    /// Promises which are waiting to be settled by a thread
+    pub promises: Rc<RefCell<HashMap<Uuid, values::Promise>>>,
+    /// Promises which were rejected, reported once the script is done if they were never awaited
+    pub rejected: Rc<RefCell<Vec<values::Promise>>>,
+    /// Timers which are still running, one-shot timers are removed once their job has ran
+    pub timers: Rc<RefCell<HashMap<String, native::timers::Timer>>>,
+    /// Whether the main script is ran by the bytecode VM rather than by walking the nodes
+    pub use_vm: bool,
+    /// Times function calls, only set when ran with --profile so it costs nothing otherwise
+    pub profiler: Option<Rc<RefCell<profiler::Profiler>>>,
+    /// How many Zephyr calls are currently running
+    pub call_depth: Rc<Cell<usize>>,
+    pub max_call_depth: usize,
     pub prototype_store: prototype_store::PrototypeStore,
     pub function_ids: Rc<RefCell<HashMap<Uuid, FunctionType>>>,
 }
 
-static NODE_TIMINGS: LazyLock<Arc<Mutex<HashMap<String, Vec<u128>>>>> =
-    LazyLock::new(|| Arc::from(Mutex::from(HashMap::new())));
-
-fn format_duration(nanos: u128) -> String {
-    if nanos >= 1_000_000_000 {
-        format!("{:.3} s", nanos as f64 / 1_000_000_000.0) // Convert to seconds
-    } else if nanos >= 1_000_000 {
-        format!("{:.3} ms", nanos as f64 / 1_000_000.0) // Convert to milliseconds
-    } else if nanos >= 1_000 {
-        format!("{:.3} µs", nanos as f64 / 1_000.0) // Convert to microseconds
-    } else {
-        format!("{} ns", nanos) // Keep as nanoseconds
-    }
-}
-
 impl Interpreter {
     pub fn new(file_name: String) -> Self {
         let global_scope = Rc::from(RefCell::from(Scope::new(file_name)));
@@ -100,10 +107,18 @@ impl Interpreter {
             global_scope: global_scope.clone(),
             scope: global_scope.clone(),
             module_cache: HashMap::new(),
-            thread_count: 0,
+            thread_count: Rc::default(),
             mspc: None,
+            receiver: None,
+            promises: Rc::default(),
+            rejected: Rc::default(),
+            timers: Rc::default(),
+            use_vm: false,
+            profiler: None,
+            call_depth: Rc::default(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
             prototype_store: prototype_store::PrototypeStore::new(),
-            function_ids: Rc::default()
+            function_ids: Rc::default(),
         };
 
         let library_files: Vec<(&str, &str)> = vec![
@@ -118,6 +133,10 @@ impl Interpreter {
             include_lib!("./lib/math.zr"),
             include_lib!("./lib/numbers.zr"),
             include_lib!("./lib/enums.zr"),
+            include_lib!("./lib/generators.zr"),
+            include_lib!("./lib/net.zr"),
+            include_lib!("./lib/timers.zr"),
+            include_lib!("./lib/memory.zr"),
         ];
 
         for lib in library_files {
@@ -130,16 +149,19 @@ impl Interpreter {
             )
             .produce_ast()
             .unwrap_or_else(|e| panic!("{}", e._visualise(lib.0.to_string())));
+            let mut parsed = match parsed {
+                Node::Block(b) => Node::ExportedBlock(nodes::ExportedBlock {
+                    nodes: b.nodes,
+                    location: b.location,
+                }),
+                _ => panic!(),
+            };
+            Interpreter::resolve(&mut parsed, &lib_scope)
+                .unwrap_or_else(|e| panic!("{}", e._visualise(lib.0.to_string())));
 
             std::mem::swap(&mut interpreter.scope, &mut lib_scope.clone());
             interpreter
-                .run_exported(match parsed {
-                    Node::Block(b) => nodes::ExportedBlock {
-                        nodes: b.nodes,
-                        location: b.location,
-                    },
-                    _ => panic!(),
-                })
+                .run(parsed)
                 .unwrap_or_else(|e| panic!("{}", e._visualise(lib.0.to_string())));
             std::mem::swap(&mut interpreter.scope, &mut lib_scope.clone());
 
@@ -148,11 +170,33 @@ impl Interpreter {
                 let value = finished_scope.lookup(name.clone(), None).unwrap();
                 global_scope
                     .borrow_mut()
-                    .insert(name.clone(), Variable::from(value), None)
+                    .insert(
+                        name.clone(),
+                        Variable {
+                            is_const: true,
+                            value,
+                        },
+                        None,
+                    )
                     .unwrap();
             }
         }
 
+        // The standard library is done setting up, so stop user scripts from changing it
+        for proto in interpreter
+            .prototype_store
+            .prototype_mapping
+            .borrow()
+            .values()
+        {
+            proto.wrap().freeze(false);
+        }
+        global_scope
+            .borrow()
+            .lookup("__zephyr_native", None)
+            .unwrap()
+            .freeze(false);
+
         interpreter
     }
 
@@ -162,72 +206,19 @@ impl Interpreter {
             Receiver<zephyr_mspc::MspcSendType>,
         ) = channel();
         self.mspc = Some(zephyr_mspc::MspcChannel { mspc: tx });
+        self.receiver = Some(Rc::from(rx));
 
-        let result = self.run(node);
+        let result = match self.use_vm {
+            true => self.run_compiled(&node),
+            false => self.run(node),
+        };
+        let result = self.run_event_loop(&|| false).and(result);
+        self.report_rejections();
 
-        /*if self.thread_count == 0 {
-            loop {
-                match rx.try_recv() {
-                    Ok(value) => match value {
-                        MspcSendType::ThreadMessage(job) => {
-                            self.run_function(job.func, job.args, NO_LOCATION.clone())?;
-                        }
-                        _ => (),
-                    },
-                    Err(_) => break,
-                }
-            }
-            return result;
-        }*/
-
-        loop {
-            match rx.try_recv() {
-                Ok(value) => match value {
-                    zephyr_mspc::MspcSendType::ThreadCreate => self.thread_count += 1,
-                    zephyr_mspc::MspcSendType::ThreadDestroy => self.thread_count -= 1,
-                    zephyr_mspc::MspcSendType::ThreadMessage(job) => {
-                        let Some(func) = self.function_ids.borrow().get(&job.func).cloned() else {
-                            panic!("Function id was not found in the hash map {}", job.func)
-                        };
-
-                        self.run_function(func.clone(), job.args.into(), NO_LOCATION.clone())?;
-                    }
-                },
-                Err(TryRecvError::Empty) => {
-                    if self.thread_count == 0 {
-                        break;
-                    }
-                    std::thread::yield_now();
-                }
-                Err(TryRecvError::Disconnected) => {
-                    break;
-                }
-            }
+        if let Some(profiler) = &self.profiler {
+            profiler.borrow_mut().finish();
         }
 
-        let data = NODE_TIMINGS.lock().unwrap();
-        let mut sorted_vec: Vec<(String, u128)> = data
-            .clone()
-            .into_iter()
-            .map(|(name, values)| {
-                let avg = values.iter().sum::<u128>() / values.len() as u128;
-                (name, avg)
-            })
-            .collect();
-
-        sorted_vec.sort_by_key(|&(_, avg)| Reverse(avg));
-
-        // for (key, time) in sorted_vec {
-        //     println!(
-        //         "{}: {} ({})",
-        //         key,
-        //         format_duration(time),
-        //         data.get(&key).unwrap().len()
-        //     );
-        // }
-
-        // println!("{:?}", data.keys());
-
         result
     }
 
@@ -235,6 +226,23 @@ impl Interpreter {
         std::mem::replace(&mut self.scope, scope)
     }
 
+    /// Prints a value for debug, using __str if the value defines it
+    pub fn debug_value(
+        &mut self,
+        value: &RuntimeValue,
+        location: &Location,
+    ) -> Result<(), ZephyrError> {
+        match self.find_overload(value, "__str") {
+            Some(_) => println!("{}", self.stringify(value, location)?),
+            None => println!(
+                "{}{}",
+                self.stringify_with(value, true, true, location)?,
+                value.tags_string(true)
+            ),
+        }
+        Ok(())
+    }
+
     pub fn insert_function(&self, f: FunctionType) -> Uuid {
         let uuid = Uuid::new_v4();
         self.function_ids.borrow_mut().insert(uuid, f);
@@ -242,15 +250,20 @@ impl Interpreter {
     }
 
     pub fn run(&mut self, node: Node) -> R {
-        let start = Instant::now();
-        let result = match node.clone() {
+        match node.clone() {
             // ----- conditionals -----
             Node::If(expr) => self.run_if(expr),
             Node::Match(expr) => self.run_match(expr),
 
+            // ----- errors -----
+            Node::Try(expr) => self.run_try(expr),
+            Node::Throw(expr) => self.run_throw(expr),
+
             // ----- functions -----
             Node::Function(expr) => self.run_make_function(expr),
             Node::Call(expr) => self.run_call(expr),
+            Node::Coalesce(expr) => self.run_coalesce(expr),
+            Node::Await(expr) => self.run_await(expr),
 
             // ----- helpers -----
             Node::Block(expr) => self.run_block(expr),
@@ -272,22 +285,41 @@ impl Interpreter {
             Node::Declare(expr) => self.run_declare(expr),
             Node::Assign(expr) => self.run_assign(expr),
             Node::Enum(expr) => self.run_enum(expr),
+            Node::Struct(expr) => self.run_struct(expr),
+            Node::Yield(_) => Err(ZephyrError {
+                message: "yield can only be used as a statement of a generator's body, not inside of an expression"
+                    .to_string(),
+                code: ErrorCode::InvalidOperation,
+                location: None,
+            }),
 
             // ----- imports -----
             Node::Import(expr) => self.run_import(expr),
             Node::Export(expr) => self.run_export(expr),
 
             Node::Interrupt(expr) => match expr.t {
-                InterruptType::Continue => Err(ZephyrError {
-                    message: "Cannot continue here".to_string(),
-                    code: ErrorCode::Continue,
+                InterruptType::Continue(label) => Err(ZephyrError {
+                    message: match label {
+                        Some(ref label) => format!("No loop labeled {} to continue", label.value),
+                        None => "Cannot continue here".to_string(),
+                    },
+                    code: ErrorCode::Continue(label.map(|x| x.value)),
                     location: Some(expr.location.clone()),
                 }),
-                InterruptType::Break => Err(ZephyrError {
-                    message: "Cannot break here".to_string(),
-                    code: ErrorCode::Break,
+                InterruptType::Break(label) => Err(ZephyrError {
+                    message: match label {
+                        Some(ref label) => {
+                            format!("No loop labeled {} to break out of", label.value)
+                        }
+                        None => "Cannot break here".to_string(),
+                    },
+                    code: ErrorCode::Break(label.map(|x| x.value)),
                     location: Some(expr.location.clone()),
                 }),
+                InterruptType::Return(Some(val)) if expr.tail_call => match *val {
+                    Node::Call(call) => self.run_tail_call(call),
+                    _ => unreachable!("Only calls are marked as tail calls"),
+                },
                 InterruptType::Return(val) => {
                     let value = if let Some(v) = val {
                         Some(self.run(*v)?)
@@ -304,19 +336,25 @@ impl Interpreter {
             },
 
             Node::Array(expr) => self.run_array(expr),
+            Node::Spread(expr) => Err(ZephyrError {
+                message: "Can only spread inside of arrays, objects or call arguments".to_string(),
+                code: ErrorCode::InvalidOperation,
+                location: Some(expr.location),
+            }),
             Node::Object(expr) => self.run_object(expr),
 
             Node::Member(expr) => self.run_member(expr, None),
 
             Node::Number(expr) => Ok(values::Number::new(expr.value).wrap()),
             Node::ZString(expr) => Ok(values::ZString::new(expr.value).wrap()),
+            Node::TemplateString(expr) => self.run_template_string(expr),
             Node::Symbol(expr) => {
                 Ok(
-                    match self
-                        .scope
-                        .borrow()
-                        .lookup(expr.value, Some(expr.location))?
-                    {
+                    match self.scope.borrow().lookup_at(
+                        &expr.value,
+                        expr.address,
+                        Some(expr.location),
+                    )? {
                         RuntimeValue::Export(r) => r.inner()?,
                         v => v,
                     },
@@ -333,7 +371,7 @@ impl Interpreter {
 
             Node::Debug(expr) => {
                 let result = self.run(*expr.node)?;
-                println!("{}", result.to_string(true, true, true).unwrap());
+                self.debug_value(&result, &expr.location)?;
                 return Ok(Null::new().wrap());
             }
         }
@@ -344,36 +382,46 @@ impl Interpreter {
                 err.location = Some(node.location().clone())
             }
             err
-        });
-
-        let done = start.elapsed().as_nanos();
-        let key = format!("Node:{:?}", node).split("(").collect::<Vec<&str>>()[0].to_string();
-        insert_node_timing(key, done);
-
-        result
+        })
     }
 }
+use crate::runtime::values::FunctionType;
 
-pub fn insert_node_timing(key: String, time: u128) {
-    let mut lock = NODE_TIMINGS.lock().unwrap();
-    if let Some(val) = lock.get_mut(&key) {
-        val.push(time);
-    } else {
-        lock.insert(key, vec![time]);
-    }
-}
-
-macro_rules! time_this {
-    ($name:expr, $what:expr) => {{
-        let time = Instant::now();
-
-        let result = $what;
+#[cfg(test)]
+pub mod test {
+    use std::thread;
 
-        insert_node_timing($name, time.elapsed().as_nanos());
+    use crate::{
+        lexer::{lexer::lex, tokens::NO_LOCATION},
+        parser::Parser,
+    };
 
-        result
-    }};
+    use super::Interpreter;
+
+    /// Runs the code with either the tree-walker or the VM, giving the result as a string, using
+    /// __str, or the error message
+    pub fn run(code: &str, use_vm: bool) -> String {
+        let code = code.to_string();
+        thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(move || {
+                let mut node = Parser::new(lex(&code, String::new()).unwrap(), String::new())
+                    .produce_ast()
+                    .unwrap();
+                let mut interpreter = Interpreter::new(String::new());
+                Interpreter::resolve(&mut node, &interpreter.scope).unwrap();
+                interpreter.use_vm = use_vm;
+
+                match interpreter
+                    .base_run(node)
+                    .and_then(|x| interpreter.stringify(&x, &NO_LOCATION))
+                {
+                    Ok(value) => value,
+                    Err(err) => format!("error: {}", err.message),
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    }
 }
-
-pub(crate) use time_this;
-use crate::runtime::values::FunctionType;
@@ -9,12 +9,39 @@ use std::sync::Arc;
 use super::{make_no_args_error, NativeExecutionContext};
 
 pub fn all() -> Vec<(String, RuntimeValue)> {
-    vec![add_native!("iter", iter)]
+    vec![
+        add_native!("iter", iter),
+        add_native!("freeze", freeze),
+        add_native!("is_frozen", is_frozen),
+    ]
 }
 
-pub fn iter(ctx: NativeExecutionContext) -> R {
+pub fn iter(mut ctx: NativeExecutionContext) -> R {
     match &ctx.args[..] {
-        [r] => Ok(values::Array::new(r.iter()?).wrap()),
+        [r] => {
+            Ok(values::Array::new(ctx.interpreter.collect_iter(r.clone(), &ctx.location)?).wrap())
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
+
+pub fn freeze(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [value] => {
+            value.freeze(false);
+            Ok(value.clone())
+        }
+        [value, RuntimeValue::Boolean(deep)] => {
+            value.freeze(deep.value);
+            Ok(value.clone())
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
+
+pub fn is_frozen(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [value] => Ok(values::Boolean::new(*value.options().frozen.borrow()).wrap()),
         _ => Err(make_no_args_error(ctx.location)),
     }
 }
@@ -9,10 +9,11 @@ use std::sync::Arc;
 use super::{make_no_args_error, NativeExecutionContext};
 
 pub fn all() -> Vec<(String, RuntimeValue)> {
-    vec![add_native!(
-        "get_enum_varient_inner",
-        get_enum_varient_inner
-    )]
+    vec![
+        add_native!("get_enum_varient_inner", get_enum_varient_inner),
+        add_native!("get_enum_variant_name", get_enum_variant_name),
+        add_native!("get_enum_variants", get_enum_variants),
+    ]
 }
 
 fn get_enum_varient_inner(ctx: NativeExecutionContext) -> R {
@@ -21,3 +22,34 @@ fn get_enum_varient_inner(ctx: NativeExecutionContext) -> R {
         _ => Err(make_no_args_error(ctx.location)),
     }
 }
+
+fn get_enum_variant_name(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [RuntimeValue::EnumVariant(val)] => {
+            let name = val.full_name();
+            Ok(values::ZString::new(name.rsplit('.').next().unwrap_or(name).to_string()).wrap())
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
+
+/// Gives the variants of an enum in the order they were declared
+fn get_enum_variants(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [RuntimeValue::Object(obj)] => {
+            let Some(names) = obj.options.tags.borrow().get("__enum_variants").cloned() else {
+                return Err(make_no_args_error(ctx.location));
+            };
+            let items = obj.items.borrow();
+
+            Ok(values::Array::new(
+                names
+                    .split(',')
+                    .filter_map(|x| items.get(x).cloned())
+                    .collect::<Vec<RuntimeValue>>(),
+            )
+            .wrap())
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
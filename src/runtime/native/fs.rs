@@ -1,15 +1,25 @@
-use crate::runtime::{
-    native::add_native,
-    values::{self, RuntimeValue, RuntimeValueUtils},
-    R,
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    runtime::{
+        native::add_native,
+        values::{self, RuntimeValue, RuntimeValueUtils},
+        R,
+    },
 };
 
-use std::{fs, sync::Arc};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Lines},
+    sync::{Arc, Mutex},
+};
 
 use super::{make_no_args_error, NativeExecutionContext};
 
 pub fn all() -> Vec<(String, RuntimeValue)> {
-    vec![add_native!("file_exists", file_exists)]
+    vec![
+        add_native!("file_exists", file_exists),
+        add_native!("file_lines_reader", file_lines_reader),
+    ]
 }
 
 pub fn file_exists(ctx: NativeExecutionContext) -> R {
@@ -20,3 +30,35 @@ pub fn file_exists(ctx: NativeExecutionContext) -> R {
         _ => Err(make_no_args_error(ctx.location)),
     }
 }
+
+/// Opens a file, giving a native function which reads the next line each time it is called,
+/// giving null at the end of the file
+pub fn file_lines_reader(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [RuntimeValue::ZString(path)] => {
+            let file = fs::File::open(&path.value).map_err(|err| ZephyrError {
+                message: format!("Cannot read {}: {}", path.value, err.kind()),
+                code: ErrorCode::CannotResolve,
+                location: Some(ctx.location.clone()),
+            })?;
+            let lines: Mutex<Lines<BufReader<fs::File>>> = Mutex::new(BufReader::new(file).lines());
+
+            Ok(
+                values::NativeFunction::new(Arc::new(move |ctx: NativeExecutionContext| {
+                    let mut lines = lines.lock().unwrap();
+                    match lines.next() {
+                        Some(Ok(line)) => Ok(values::ZString::new(line).wrap()),
+                        Some(Err(err)) => Err(ZephyrError {
+                            message: format!("Cannot read line: {}", err.kind()),
+                            code: ErrorCode::CannotResolve,
+                            location: Some(ctx.location),
+                        }),
+                        None => Ok(values::Null::new().wrap()),
+                    }
+                }))
+                .wrap(),
+            )
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
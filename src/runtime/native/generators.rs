@@ -0,0 +1,23 @@
+use crate::runtime::{
+    native::add_native,
+    values::{self, RuntimeValue, RuntimeValueUtils},
+    R,
+};
+
+use std::sync::Arc;
+
+use super::{make_no_args_error, NativeExecutionContext};
+
+pub fn all() -> Vec<(String, RuntimeValue)> {
+    vec![add_native!("generator_next", generator_next)]
+}
+
+fn generator_next(mut ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [RuntimeValue::Generator(generator)] => {
+            let generator = generator.clone();
+            ctx.interpreter.generator_next(&generator, &ctx.location)
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
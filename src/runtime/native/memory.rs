@@ -0,0 +1,44 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::runtime::{
+    gc,
+    native::add_native,
+    values::{self, RuntimeValue, RuntimeValueUtils},
+    R,
+};
+
+use super::{make_no_args_error, NativeExecutionContext};
+
+pub fn all() -> Vec<(String, RuntimeValue)> {
+    vec![
+        add_native!("gc", collect),
+        add_native!("memory_stats", memory_stats),
+    ]
+}
+
+/// Frees the values which are only kept alive by cycles, giving how many were freed
+pub fn collect(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [] => Ok(values::Number::new(gc::collect() as f64).wrap()),
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
+
+pub fn memory_stats(ctx: NativeExecutionContext) -> R {
+    if !ctx.args.is_empty() {
+        return Err(make_no_args_error(ctx.location));
+    }
+
+    let stats = gc::stats();
+    Ok(values::Object::new(HashMap::from(
+        [
+            ("scopes", stats.scopes),
+            ("objects", stats.objects),
+            ("arrays", stats.arrays),
+            ("collections", stats.collections),
+            ("freed", stats.freed),
+        ]
+        .map(|(name, value)| (name.to_string(), values::Number::new(value as f64).wrap())),
+    ))
+    .wrap())
+}
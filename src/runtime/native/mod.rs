@@ -9,7 +9,9 @@ pub mod basics;
 pub mod enums;
 pub mod events;
 pub mod fs;
+pub mod generators;
 pub mod math;
+pub mod memory;
 pub mod module;
 pub mod native_util;
 pub mod proto;
@@ -17,6 +19,7 @@ pub mod strings;
 pub mod tags;
 pub mod test;
 pub mod tcp;
+pub mod timers;
 
 pub fn all() -> Vec<(String, RuntimeValue)> {
     vec![]
@@ -32,7 +35,10 @@ pub fn all() -> Vec<(String, RuntimeValue)> {
         .chain(strings::all().iter().cloned())
         .chain(math::all().iter().cloned())
         .chain(enums::all().iter().cloned())
+        .chain(generators::all().iter().cloned())
         .chain(tcp::all().iter().cloned())
+        .chain(timers::all().iter().cloned())
+        .chain(memory::all().iter().cloned())
         .collect()
 }
 
@@ -39,6 +39,7 @@ pub fn get_proto_obj_of(ctx: NativeExecutionContext) -> R {
 pub fn set_proto_ref(ctx: NativeExecutionContext) -> R {
     match &ctx.args[..] {
         [val, RuntimeValue::Object(r)] => {
+            val.check_frozen(&ctx.location)?;
             let key = format!("user::{}", uuid::Uuid::new_v4());
             ctx.interpreter.prototype_store.set(key.clone(), r.clone());
             *val.options().proto.borrow_mut() = Some(key.clone());
@@ -20,7 +20,10 @@ use std::{
 use crate::runtime::native::native_util::expect_one_arg;
 
 pub fn all() -> Vec<(String, RuntimeValue)> {
-    vec![add_native!("create_tcp_stream", create_tcp_stream)]
+    vec![
+        add_native!("create_tcp_stream", create_tcp_stream),
+        add_native!("tcp_request", tcp_request),
+    ]
 }
 
 from_runtime_object!(TcpStreamOptions {
@@ -129,3 +132,40 @@ pub fn create_tcp_stream(ctx: NativeExecutionContext) -> R {
     ]))
     .wrap())
 }
+
+from_runtime_object!(TcpRequestOptions {
+    url: String,
+    presend: Vec<u8>,
+});
+
+/// Sends presend to url on another thread, giving a promise of everything received until the connection closes
+pub fn tcp_request(ctx: NativeExecutionContext) -> R {
+    let options = TcpRequestOptions::from_runtime_value(expect_one_arg!(ctx.args))?;
+
+    let promise = ctx.interpreter.make_thread_promise();
+    let id = promise.id;
+    let mut channel = ctx.interpreter.mspc.clone().unwrap();
+
+    handle_thread!(channel, {
+        let result = TcpStream::connect(&options.url).and_then(|mut stream| {
+            stream.write_all(&options.presend)?;
+
+            let mut received_data = Vec::new();
+            stream.read_to_end(&mut received_data)?;
+            Ok(received_data)
+        });
+
+        channel.settle_promise(
+            id,
+            result
+                .map(|data| {
+                    ThreadRuntimeValue::new(ThreadInnerValue::ZString(
+                        String::from_utf8_lossy(&data).to_string(),
+                    ))
+                })
+                .map_err(|e| format!("TCP request to {} failed: {}", options.url, e)),
+        );
+    });
+
+    Ok(promise.wrap())
+}
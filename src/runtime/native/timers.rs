@@ -0,0 +1,118 @@
+use std::{
+    sync::{
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    errors::{ErrorCode, ZephyrError},
+    lexer::tokens::Location,
+    runtime::{
+        native::{add_native, native_util::handle_thread},
+        values::{self, FunctionType, RuntimeValue, RuntimeValueUtils},
+        zephyr_mspc::Job,
+        R,
+    },
+};
+
+use super::{make_no_args_error, NativeExecutionContext};
+
+pub fn all() -> Vec<(String, RuntimeValue)> {
+    vec![
+        add_native!("set_timeout", set_timeout),
+        add_native!("set_interval", set_interval),
+        add_native!("clear_timer", clear_timer),
+        add_native!("sleep", sleep),
+    ]
+}
+
+/// A timer which is still running
+pub struct Timer {
+    /// Dropping it stops the timer's thread
+    pub clear: Sender<()>,
+    /// The id of the function in function_ids
+    pub func: Uuid,
+    pub repeat: bool,
+}
+
+fn duration(ms: f64, location: &Location) -> Result<Duration, ZephyrError> {
+    Duration::try_from_secs_f64(ms.max(0f64) / 1000f64).map_err(|_| ZephyrError {
+        message: format!("{} is not a valid number of milliseconds", ms),
+        code: ErrorCode::RangeError,
+        location: Some(location.clone()),
+    })
+}
+
+/// Starts a thread which posts a job for func every ms, or only once if repeat is not set
+/// The timer's thread stops as soon as it is cleared, so it does not keep the event loop waiting
+fn start_timer(ctx: NativeExecutionContext, repeat: bool) -> R {
+    let (func, ms) = match &ctx.args[..] {
+        [func, RuntimeValue::Number(ms)] => (FunctionType::from(func.clone())?, ms.value),
+        _ => return Err(make_no_args_error(ctx.location)),
+    };
+
+    let wait = duration(ms, &ctx.location)?;
+    let func = ctx.interpreter.insert_function(func);
+    let id = uuid::Uuid::new_v4().to_string();
+    let (clear_tx, clear_rx) = channel::<()>();
+    ctx.interpreter.timers.borrow_mut().insert(
+        id.clone(),
+        Timer {
+            clear: clear_tx,
+            func,
+            repeat,
+        },
+    );
+
+    let mut channel = ctx.interpreter.mspc.clone().unwrap();
+    let timer = id.clone();
+
+    handle_thread!(channel, {
+        while let Err(RecvTimeoutError::Timeout) = clear_rx.recv_timeout(wait) {
+            channel.thread_message(Job {
+                func,
+                args: vec![].into(),
+                timer: Some(timer.clone()),
+            });
+
+            if !repeat {
+                break;
+            }
+        }
+    });
+
+    Ok(values::ZString::new(id).wrap())
+}
+
+pub fn set_timeout(ctx: NativeExecutionContext) -> R {
+    start_timer(ctx, false)
+}
+
+pub fn set_interval(ctx: NativeExecutionContext) -> R {
+    start_timer(ctx, true)
+}
+
+pub fn clear_timer(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [RuntimeValue::ZString(id)] => {
+            ctx.interpreter.clear_timer(&id.value);
+            Ok(values::Null::new().wrap())
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
+
+pub fn sleep(ctx: NativeExecutionContext) -> R {
+    match &ctx.args[..] {
+        [RuntimeValue::Number(ms)] => {
+            thread::sleep(duration(ms.value, &ctx.location)?);
+            Ok(values::Null::new().wrap())
+        }
+        _ => Err(make_no_args_error(ctx.location)),
+    }
+}
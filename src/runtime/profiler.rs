@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{lexer::tokens::Location, util::colors};
+
+/// How long a function took over all of its calls
+#[derive(Default)]
+struct FunctionStats {
+    calls: usize,
+    /// Time spent in the function itself, not counting the functions it called
+    self_time: Duration,
+    /// Time from the outermost call to it returning, so recursion is not counted twice
+    total_time: Duration,
+    /// How many calls of it are currently running
+    active: usize,
+}
+
+struct ProfileFrame {
+    name: Rc<str>,
+    start: Instant,
+    children: Duration,
+}
+
+/// Times every call of a Zephyr function, only created when ran with --profile
+pub struct Profiler {
+    stack: Vec<ProfileFrame>,
+    functions: HashMap<Rc<str>, FunctionStats>,
+    /// The self time of every stack that was seen, keyed by the function names joined by ;
+    folded: HashMap<String, Duration>,
+    /// Where to write the folded stacks
+    output: String,
+}
+
+impl Profiler {
+    pub fn new(output: String) -> Self {
+        let mut profiler = Profiler {
+            stack: vec![],
+            functions: HashMap::new(),
+            folded: HashMap::new(),
+            output,
+        };
+
+        // Everything outside of a function is counted as the script itself
+        profiler.enter(Rc::from("<main>"));
+        profiler
+    }
+
+    /// The name a function is shown as, including where it was declared to tell apart ones with
+    /// the same name
+    pub fn function_name(name: &Option<String>, location: &Location) -> Rc<str> {
+        let file_name = location
+            .file_name
+            .as_ref()
+            .and_then(|x| Path::new(x).file_name())
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Rc::from(format!(
+            "{} ({}:{})",
+            name.as_deref().unwrap_or("<anonymous>"),
+            file_name,
+            location.line + 1
+        ))
+    }
+
+    pub fn enter(&mut self, name: Rc<str>) {
+        self.functions.entry(name.clone()).or_default().active += 1;
+        self.stack.push(ProfileFrame {
+            name,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    pub fn exit(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+
+        let elapsed = frame.start.elapsed();
+        let self_time = elapsed.saturating_sub(frame.children);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
+
+        let stats = self.functions.entry(frame.name.clone()).or_default();
+        stats.calls += 1;
+        stats.self_time += self_time;
+        stats.active -= 1;
+        if stats.active == 0 {
+            stats.total_time += elapsed;
+        }
+
+        let key = self
+            .stack
+            .iter()
+            .map(|x| &*x.name)
+            .chain([&*frame.name])
+            .collect::<Vec<&str>>()
+            .join(";");
+        *self.folded.entry(key).or_default() += self_time;
+    }
+
+    /// Stops timing the script, printing the time of each function and writing the folded stacks
+    pub fn finish(&mut self) {
+        while !self.stack.is_empty() {
+            self.exit();
+        }
+
+        let mut functions = self
+            .functions
+            .iter()
+            .filter(|(name, _)| &***name != "<main>")
+            .collect::<Vec<_>>();
+        functions.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.self_time));
+
+        let total = self
+            .functions
+            .get("<main>")
+            .map(|x| x.total_time)
+            .unwrap_or_default();
+
+        eprintln!(
+            "{}Profile, ran for {}{}",
+            colors::FG_CYAN,
+            format_duration(total),
+            colors::COLOR_RESET
+        );
+        eprintln!("{:>12} {:>12} {:>10}  function", "self", "total", "calls");
+        for (name, stats) in functions {
+            eprintln!(
+                "{:>12} {:>12} {:>10}  {}",
+                format_duration(stats.self_time),
+                format_duration(stats.total_time),
+                stats.calls,
+                name
+            );
+        }
+
+        // One line per stack with its self time in nanoseconds, which flamegraph tools take
+        let mut lines = self
+            .folded
+            .iter()
+            .map(|(stack, time)| format!("{} {}", stack, time.as_nanos()))
+            .collect::<Vec<String>>();
+        lines.sort();
+
+        match fs::write(&self.output, lines.join("\n") + "\n") {
+            Ok(_) => eprintln!("Folded stacks written to {}", self.output),
+            Err(err) => eprintln!(
+                "Failed to write the folded stacks to {}: {}",
+                self.output, err
+            ),
+        }
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos >= 1_000_000_000 {
+        format!("{:.3} s", nanos as f64 / 1_000_000_000.0)
+    } else if nanos >= 1_000_000 {
+        format!("{:.3} ms", nanos as f64 / 1_000_000.0)
+    } else if nanos >= 1_000 {
+        format!("{:.3} µs", nanos as f64 / 1_000.0)
+    } else {
+        format!("{} ns", nanos)
+    }
+}
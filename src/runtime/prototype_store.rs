@@ -18,6 +18,8 @@ impl PrototypeStore {
                     "array",
                     "number",
                     "enum",
+                    "generator",
+                    "promise",
                     "object",
                 ]
                 .iter()
@@ -3,18 +3,15 @@ use std::{
     collections::HashMap,
     rc::Rc,
     sync::{Mutex, OnceLock},
-    time::Instant,
 };
 
 use crate::{
     errors::{ErrorCode, ZephyrError},
     lexer::tokens::Location,
+    parser::nodes::Address,
 };
 
-use super::{
-    insert_node_timing, time_this,
-    values::{self, RuntimeValue, RuntimeValueUtils},
-};
+use super::values::{self, RuntimeValue, RuntimeValueUtils};
 
 static PROTOTYPE_STORE: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
 
@@ -45,7 +42,10 @@ pub type ScopeInnerType = Rc<RefCell<Scope>>;
 #[derive(Debug, Clone)]
 pub struct Scope {
     pub parent: Option<ScopeInnerType>,
-    pub variables: HashMap<String, Variable>,
+    /// The variables in the order they were declared, resolved symbols use the index
+    pub slots: Vec<(String, Variable)>,
+    /// The index of each variable in slots, for anything that is looked up by name
+    pub names: HashMap<String, usize>,
     pub exported: HashMap<String, Option<String>>,
     pub scope_type: ScopeType,
     pub file_name: String,
@@ -53,26 +53,24 @@ pub struct Scope {
 
 impl Scope {
     pub fn new(file_name: String) -> Self {
-        Scope {
+        let mut scope = Scope {
             exported: HashMap::new(),
             parent: None,
-            variables: HashMap::from([
-                (
-                    "true".to_string(),
-                    Variable::from(values::Boolean::new(true).wrap()),
-                ),
-                (
-                    "false".to_string(),
-                    Variable::from(values::Boolean::new(false).wrap()),
-                ),
-                (
-                    "null".to_string(),
-                    Variable::from(values::Null::new().wrap()),
-                ),
-            ]),
+            slots: vec![],
+            names: HashMap::new(),
             scope_type: ScopeType::Normal,
             file_name,
+        };
+
+        for (name, value) in [
+            ("true", values::Boolean::new(true).wrap()),
+            ("false", values::Boolean::new(false).wrap()),
+            ("null", values::Null::new().wrap()),
+        ] {
+            scope.insert(name, Variable::from(value), None).unwrap();
         }
+
+        scope
     }
 
     pub fn new_from_parent(parent: ScopeInnerType) -> Self {
@@ -83,13 +81,120 @@ impl Scope {
     pub fn new_from_parent_new_file_name(parent: ScopeInnerType, file_name: String) -> Self {
         Scope {
             parent: Some(parent.clone()),
-            variables: HashMap::new(),
+            slots: vec![],
+            names: HashMap::new(),
             scope_type: ScopeType::Normal,
             exported: HashMap::new(),
             file_name,
         }
     }
 
+    /// Finds the name of the variable that is exported under the given name
+    pub fn resolve_export(&self, name: &str) -> Option<String> {
+        self.exported
+            .iter()
+            .find(|(local, export_as)| export_as.as_deref().unwrap_or(local) == name)
+            .map(|(local, _)| local.clone())
+    }
+
+    /// The names the scope exports its variables under
+    pub fn exported_names(&self) -> Vec<String> {
+        self.exported
+            .iter()
+            .map(|(local, export_as)| export_as.clone().unwrap_or(local.clone()))
+            .collect()
+    }
+
+    fn get(&self, name: &str) -> Option<&Variable> {
+        self.names.get(name).map(|slot| &self.slots[*slot].1)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Variable> {
+        self.names.get(name).map(|slot| &mut self.slots[*slot].1)
+    }
+
+    /// The names of the variables in slot order, used to resolve code which will run in this scope
+    pub fn slot_names(&self) -> Vec<String> {
+        self.slots.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Runs f with the variable at the address, if it holds the variable with the name
+    ///
+    /// The slot should always hold it, but if the resolver and the scopes ever disagree this gives
+    /// None, so the variable is looked up by name instead of using the wrong one
+    fn with_slot<T>(
+        &mut self,
+        address: Address,
+        name: &str,
+        f: impl FnOnce(&mut Variable) -> T,
+    ) -> Option<T> {
+        if address.depth == 0 {
+            return match self.slots.get_mut(address.slot) {
+                Some((slot_name, variable)) if slot_name == name => Some(f(variable)),
+                _ => None,
+            };
+        }
+
+        self.parent.as_ref()?.borrow_mut().with_slot(
+            Address {
+                depth: address.depth - 1,
+                slot: address.slot,
+            },
+            name,
+            f,
+        )
+    }
+
+    fn get_slot(&self, address: Address, name: &str) -> Option<RuntimeValue> {
+        if address.depth == 0 {
+            return match self.slots.get(address.slot) {
+                Some((slot_name, variable)) if slot_name == name => Some(variable.value.clone()),
+                _ => None,
+            };
+        }
+
+        self.parent.as_ref()?.borrow().get_slot(
+            Address {
+                depth: address.depth - 1,
+                slot: address.slot,
+            },
+            name,
+        )
+    }
+
+    /// Looks up a variable using the address from the resolver, falling back to its name
+    pub fn lookup_at(
+        &self,
+        name: &str,
+        address: Option<Address>,
+        location: Option<Location>,
+    ) -> Result<RuntimeValue, ZephyrError> {
+        if let Some(value) = address.and_then(|address| self.get_slot(address, name)) {
+            return Ok(value);
+        }
+
+        self.lookup(name, location)
+    }
+
+    /// Modifies a variable using the address from the resolver, falling back to its name
+    pub fn modify_at(
+        &mut self,
+        name: &str,
+        address: Option<Address>,
+        value: RuntimeValue,
+        location: Option<Location>,
+    ) -> Result<(), ZephyrError> {
+        if let Some(address) = address {
+            if let Some(result) = self.with_slot(address, name, |variable| {
+                assign(variable, name, value.clone(), &location)
+            }) {
+                return result;
+            }
+        }
+
+        self.modify(name, value, location)
+    }
+
     pub fn lookup<T: Into<String>>(
         &self,
         name: T,
@@ -97,19 +202,19 @@ impl Scope {
     ) -> Result<RuntimeValue, ZephyrError> {
         let name = name.into();
 
-        let mut scope = Some(Rc::from(RefCell::from(self.clone())));
+        if let Some(val) = self.get(&name) {
+            return Ok(val.value.clone());
+        }
+
+        let mut scope = self.parent.clone();
 
-        while let Some(s) = scope.clone() {
+        while let Some(s) = scope {
             let lock = s.borrow();
-            if let Some(val) = lock.variables.get(&name) {
+            if let Some(val) = lock.get(&name) {
                 return Ok(val.value.clone());
             }
 
-            if let Some(ref parent) = lock.parent {
-                scope = Some(parent.clone());
-            } else {
-                break;
-            }
+            scope = lock.parent.clone();
         }
 
         Err(ZephyrError {
@@ -130,7 +235,7 @@ impl Scope {
             return Ok(());
         }
 
-        if self.variables.contains_key(&name) {
+        if self.names.contains_key(&name) {
             return Err(ZephyrError {
                 code: ErrorCode::AlreadyDefined,
                 message: format!("Variable {} already exists in the current scope", name),
@@ -138,7 +243,8 @@ impl Scope {
             });
         }
 
-        self.variables.insert(name, variable);
+        self.names.insert(name.clone(), self.slots.len());
+        self.slots.push((name, variable));
         Ok(())
     }
 
@@ -149,32 +255,47 @@ impl Scope {
         location: Option<Location>,
     ) -> Result<(), ZephyrError> {
         let name = name.into();
-        time_this!(
-            "Mini:ScopeModify".to_string(),
-            (|| {
-                let mut scope = Some(Rc::from(RefCell::from(self.clone())));
-
-                while let Some(s) = scope.clone() {
-                    let mut lock = s.borrow_mut();
-                    if let Some(val) = lock.variables.get_mut(&name) {
-                        val.value = value;
-                        return Ok(());
-                    }
-
-                    if let Some(ref parent) = lock.parent {
-                        scope = Some(parent.clone());
-                    }
-                }
-
-                Err(ZephyrError {
-                    code: ErrorCode::UnknownReference,
-                    message: format!("Cannot find variable {} in the current scope", name),
-                    location,
-                })
-            })()
-        )
+
+        if let Some(val) = self.get_mut(&name) {
+            return assign(val, &name, value, &location);
+        }
+
+        let mut scope = self.parent.clone();
+
+        while let Some(s) = scope {
+            let mut lock = s.borrow_mut();
+            if let Some(val) = lock.get_mut(&name) {
+                return assign(val, &name, value, &location);
+            }
+
+            scope = lock.parent.clone();
+        }
+
+        Err(ZephyrError {
+            code: ErrorCode::UnknownReference,
+            message: format!("Cannot find variable {} in the current scope", name),
+            location,
+        })
     }
 }
+fn assign(
+    variable: &mut Variable,
+    name: &str,
+    value: RuntimeValue,
+    location: &Option<Location>,
+) -> Result<(), ZephyrError> {
+    if variable.is_const {
+        return Err(ZephyrError {
+            code: ErrorCode::ConstantAssignment,
+            message: format!("Cannot assign to constant {}", name),
+            location: location.clone(),
+        });
+    }
+
+    variable.value = value;
+    Ok(())
+}
+
 /*
 pub struct PrototypeStore {}
 
@@ -1,5 +1,7 @@
 use std::{cell::RefCell, rc::Rc};
 
+use crate::runtime::gc;
+
 use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
 
 #[derive(Debug, Clone)]
@@ -10,8 +12,10 @@ pub struct Array {
 
 impl Array {
     pub fn new(items: Vec<RuntimeValue>) -> Self {
+        let items = Rc::from(RefCell::from(items));
+        gc::track_array(&items);
         Array {
-            items: Rc::from(RefCell::from(items)),
+            items,
             options: RuntimeValueDetails::with_proto("array".to_string()),
         }
     }
@@ -7,6 +7,8 @@ pub struct RuntimeValueDetails {
     pub tags: Rc<RefCell<HashMap<String, String>>>,
     pub proto: Rc<RefCell<Option<String>>>,
     pub proto_value: Option<Box<RuntimeValue>>,
+    /// Frozen values cannot have their items or prototype changed
+    pub frozen: Rc<RefCell<bool>>,
 }
 
 impl RuntimeValueDetails {
@@ -24,6 +26,7 @@ impl Default for RuntimeValueDetails {
             tags: Rc::from(RefCell::from(HashMap::default())),
             proto: Rc::from(RefCell::from(None)),
             proto_value: None,
+            frozen: Rc::from(RefCell::from(false)),
         }
     }
 }
@@ -1,4 +1,8 @@
-use crate::util::colors;
+use crate::{
+    errors::ZephyrError,
+    lexer::tokens::{Comparison, Location},
+    util::colors,
+};
 
 use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
 
@@ -6,6 +10,8 @@ use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
 pub struct EnumVariant {
     pub inner: Box<RuntimeValue>,
     pub enum_id: String,
+    /// The declared fields of a shaped variant, in order, the payload is an object of them
+    pub fields: Option<Vec<String>>,
     pub options: RuntimeValueDetails,
 }
 
@@ -14,9 +20,54 @@ impl EnumVariant {
         Self {
             inner: Box::new(inner),
             enum_id,
+            fields: None,
             options: RuntimeValueDetails::with_proto("enum".to_string()),
         }
     }
+
+    /// The readable name of the variant, e.g. Shape.Circle
+    pub fn full_name(&self) -> &str {
+        EnumVariant::name_of_id(&self.enum_id)
+    }
+
+    /// Whether the payloads of two variants are equal, shaped payloads are compared field by field
+    pub fn payload_eq(
+        &self,
+        other: &EnumVariant,
+        location: Option<Location>,
+    ) -> Result<bool, ZephyrError> {
+        match (&*self.inner, &*other.inner) {
+            (RuntimeValue::Object(l), RuntimeValue::Object(r)) => {
+                let (l, r) = (l.items.borrow(), r.items.borrow());
+                if l.len() != r.len() {
+                    return Ok(false);
+                }
+
+                for (key, value) in l.iter() {
+                    match r.get(key) {
+                        Some(other) => {
+                            if !value.compare_with(
+                                other.clone(),
+                                Comparison::Eq,
+                                location.clone(),
+                            )? {
+                                return Ok(false);
+                            }
+                        }
+                        None => return Ok(false),
+                    }
+                }
+
+                Ok(true)
+            }
+            (l, r) => l.compare_with(r.clone(), Comparison::Eq, location),
+        }
+    }
+
+    /// Gets the readable name from an enum id, ids are formatted as Enum.Variant__unique
+    pub fn name_of_id(id: &str) -> &str {
+        id.split("__").next().unwrap_or(id)
+    }
 }
 
 impl RuntimeValueUtils for EnumVariant {
@@ -33,23 +84,49 @@ impl RuntimeValueUtils for EnumVariant {
         is_display: bool,
         color: bool,
     ) -> Result<String, crate::errors::ZephyrError> {
+        // Payloadless variants are just shown by their name
+        if matches!(*self.inner, RuntimeValue::Null(_)) {
+            return Ok(match color {
+                true => format!(
+                    "{}{}{}",
+                    colors::FG_CYAN,
+                    self.full_name(),
+                    colors::COLOR_RESET
+                ),
+                false => self.full_name().to_string(),
+            });
+        }
+
+        let inner = match (&self.fields, &*self.inner) {
+            (Some(fields), RuntimeValue::Object(obj)) => {
+                let items = obj.items.borrow();
+                let mut parts: Vec<String> = vec![];
+                for field in fields {
+                    if let Some(value) = items.get(field) {
+                        parts.push(format!(
+                            "{}: {}",
+                            field,
+                            value.to_string(is_display, color, false)?
+                        ));
+                    }
+                }
+                parts.join(", ")
+            }
+            _ => self.inner.to_string(is_display, color, false)?,
+        };
+
         Ok(match color {
             true => format!(
-                "{}EnumVariant<{}{}({}{}{}){}>{}",
+                "{}{}{}({}{}{}){}",
                 colors::FG_CYAN,
+                self.full_name(),
                 colors::FG_GRAY,
-                self.enum_id,
                 colors::COLOR_RESET,
-                self.inner.to_string(is_display, color, false)?,
+                inner,
                 colors::FG_GRAY,
-                colors::FG_CYAN,
                 colors::COLOR_RESET
             ),
-            false => format!(
-                "EnumVariant<{}({})>",
-                self.enum_id,
-                self.inner.to_string(is_display, color, false)?
-            ),
+            false => format!("{}({})", self.full_name(), inner),
         })
     }
 }
@@ -40,6 +40,7 @@ impl EventEmitterForThreads {
                 sender.thread_message(Job {
                     func: part.clone(),
                     args: args.clone(),
+                    timer: None,
                 });
             }
         }
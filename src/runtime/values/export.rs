@@ -1,9 +1,11 @@
+use std::collections::HashMap;
+
 use crate::{
     errors::{ErrorCode, ZephyrError},
     runtime::scope::ScopeInnerType,
 };
 
-use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
+use super::{Object, RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
 
 #[derive(Debug, Clone)]
 pub struct Export {
@@ -22,9 +24,28 @@ impl Export {
     }
 
     pub fn inner(&self) -> Result<RuntimeValue, ZephyrError> {
-        let name = self.symbol.as_ref().unwrap_or_else(|| panic!());
+        let Some(ref name) = self.symbol else {
+            // import "x" expose * as y
+            let names = self.scope.borrow().exported_names();
+            let mut items: HashMap<String, RuntimeValue> = HashMap::new();
+            for name in names {
+                let value = Export::new(self.scope.clone(), Some(name.clone())).inner()?;
+                items.insert(name, value);
+            }
+
+            return Ok(Object::new(items).wrap());
+        };
+
+        let local = self
+            .scope
+            .borrow()
+            .resolve_export(name)
+            .unwrap_or(name.clone());
 
-        match self.scope.borrow().lookup(name, None) {
+        let value = self.scope.borrow().lookup(&local, None);
+        match value {
+            // Re-exported from another module
+            Ok(RuntimeValue::Export(export)) => export.inner(),
             Ok(ok) => Ok(ok),
             Err(_) => Err(ZephyrError {
                 message: format!("Exported variable {} has not been resolved. Please move this expression to the init block, or fix the cyclic dependency.", name),
@@ -1,9 +1,13 @@
-use std::sync::{
-    mpsc::{channel, Receiver, Sender},
-    Arc,
+use std::{
+    rc::Rc,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
 };
 
 use crate::{
+    compiler::bytecode::Chunk,
     errors::{ErrorCode, ZephyrError},
     lexer::tokens::Location,
     parser::nodes,
@@ -38,10 +42,14 @@ impl FunctionType {
 
 #[derive(Debug, Clone)]
 pub struct FunctionInner {
-    pub body: nodes::Block,
+    pub body: Rc<nodes::Block>,
     pub name: Option<String>,
-    pub arguments: Vec<String>,
+    pub arguments: Rc<nodes::Arguments>,
     pub scope: ScopeInnerType,
+    pub is_generator: bool,
+    pub is_async: bool,
+    /// The compiled body, if the function was made by the VM
+    pub chunk: Option<Rc<Chunk>>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,15 +68,31 @@ impl RuntimeValueUtils for Function {
     }
 
     fn to_string(&self, _is_display: bool, color: bool) -> Result<String, ZephyrError> {
-        let string = format!(
-            "{}",
-            self.inner
-                .arguments
-                .iter()
-                .map(|x| format!("\"{}\"", x))
-                .collect::<Vec<String>>()
-                .join(", ")
-        );
+        let arguments = &self.inner.arguments;
+        let mut names = arguments
+            .positional
+            .iter()
+            .map(|x| x.name.value.clone())
+            .collect::<Vec<String>>();
+
+        if let Some(ref rest) = arguments.positional_rest {
+            names.push(format!("...{}", rest.value));
+        }
+
+        if !arguments.named.is_empty() || arguments.named_rest.is_some() {
+            names.push("%".to_string());
+            names.extend(arguments.named.iter().map(|x| x.name.value.clone()));
+
+            if let Some(ref rest) = arguments.named_rest {
+                names.push(format!("...{}", rest.value));
+            }
+        }
+
+        let string = names
+            .iter()
+            .map(|x| format!("\"{}\"", x))
+            .collect::<Vec<String>>()
+            .join(", ");
 
         Ok(match color {
             true => {
@@ -0,0 +1,49 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{errors::ZephyrError, runtime::interpreter_generators::GeneratorState, util::colors};
+
+use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
+
+/// The value given by calling a func*, it owns the suspended body so it is dropped with the value
+#[derive(Clone)]
+pub struct Generator {
+    pub options: RuntimeValueDetails,
+    pub state: Rc<RefCell<GeneratorState>>,
+}
+
+impl Generator {
+    pub fn new(state: GeneratorState) -> Self {
+        Generator {
+            options: RuntimeValueDetails::with_proto("generator".to_string()),
+            state: Rc::from(RefCell::from(state)),
+        }
+    }
+}
+
+impl RuntimeValueUtils for Generator {
+    fn type_name(&self) -> &str {
+        "generator"
+    }
+
+    fn wrap(&self) -> RuntimeValue {
+        RuntimeValue::Generator(self.clone())
+    }
+
+    fn to_string(&self, _is_display: bool, color: bool) -> Result<String, ZephyrError> {
+        Ok(match color {
+            true => format!(
+                "{}{}{}",
+                colors::FG_CYAN,
+                "Generator<>",
+                colors::COLOR_RESET
+            ),
+            false => "Generator<>".to_string(),
+        })
+    }
+}
+
+impl std::fmt::Debug for Generator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Generator")
+    }
+}
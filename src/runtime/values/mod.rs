@@ -35,9 +35,17 @@ pub use enum_variant::*;
 pub mod export;
 pub use export::*;
 
+pub mod promise;
+pub use promise::*;
+
+pub mod generator;
+pub use generator::*;
+
 pub mod struct_mapping;
 pub mod thread_crossing;
 
+use std::{collections::HashSet, rc::Rc};
+
 use crate::{
     errors::{ErrorCode, ZephyrError},
     lexer::tokens::{Comparison, Location},
@@ -92,6 +100,8 @@ pub enum RuntimeValue {
     RangeValue(RangeValue),
     EnumVariant(EnumVariant),
     Export(Export),
+    Promise(Promise),
+    Generator(Generator),
 }
 
 macro_rules! run_as_any {
@@ -110,6 +120,8 @@ macro_rules! run_as_any {
             RuntimeValue::RangeValue($i) => $e,
             RuntimeValue::EnumVariant($i) => $e,
             RuntimeValue::Export($i) => $e,
+            RuntimeValue::Promise($i) => $e,
+            RuntimeValue::Generator($i) => $e,
         }
     };
 }
@@ -140,6 +152,47 @@ impl RuntimeValue {
         run_as_any!(self, v, Box::from(v.clone()))
     }
 
+    /// Freezes the value, and everything inside of it if deep is set
+    pub fn freeze(&self, deep: bool) {
+        self.freeze_inner(deep, &mut HashSet::new());
+    }
+
+    /// seen holds the arrays / objects already visited, so cycles stop
+    fn freeze_inner(&self, deep: bool, seen: &mut HashSet<usize>) {
+        *self.options().frozen.borrow_mut() = true;
+
+        if !deep {
+            return;
+        }
+
+        match self {
+            RuntimeValue::Array(arr) if seen.insert(Rc::as_ptr(&arr.items) as usize) => {
+                for item in arr.items.borrow().iter() {
+                    item.freeze_inner(true, seen);
+                }
+            }
+            RuntimeValue::Object(obj) if seen.insert(Rc::as_ptr(&obj.items) as usize) => {
+                for item in obj.items.borrow().values() {
+                    item.freeze_inner(true, seen);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Errors if the value is frozen and so cannot be modified
+    pub fn check_frozen(&self, location: &Location) -> Result<(), ZephyrError> {
+        if *self.options().frozen.borrow() {
+            return Err(ZephyrError {
+                message: format!("Cannot modify a frozen {}", self.type_name()),
+                code: ErrorCode::Frozen,
+                location: Some(location.clone()),
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn set_proto(self, id: String) -> Self {
         let mut old_options = self.options().proto.borrow_mut();
         *old_options = Some(id);
@@ -156,20 +209,25 @@ impl RuntimeValue {
         let mut string = run_as_any!(self, v, v.to_string(is_display, color))?;
 
         if full {
-            string.push_str(&match color {
-                true => format!(
-                    "\n{}# {:?}{}",
-                    colors::FG_GRAY,
-                    self.options().tags.borrow(),
-                    colors::COLOR_RESET
-                ),
-                false => format!("\n# {:?}", self.options().tags.borrow()),
-            });
+            string.push_str(&self.tags_string(color));
         }
 
         Ok(string)
     }
 
+    /// The line showing the value's tags, added after it by to_string when full
+    pub fn tags_string(&self, color: bool) -> String {
+        match color {
+            true => format!(
+                "\n{}# {:?}{}",
+                colors::FG_GRAY,
+                self.options().tags.borrow(),
+                colors::COLOR_RESET
+            ),
+            false => format!("\n# {:?}", self.options().tags.borrow()),
+        }
+    }
+
     /// Checks whether or not the value is "truthy" following set rules
     pub fn is_truthy(&self) -> bool {
         match self {
@@ -227,6 +285,19 @@ impl RuntimeValue {
             (RuntimeValue::ZString(l), RuntimeValue::ZString(r), Comparison::Neq) => {
                 l.value != r.value
             }
+            (RuntimeValue::Boolean(l), RuntimeValue::Boolean(r), Comparison::Eq) => {
+                l.value == r.value
+            }
+            (RuntimeValue::Boolean(l), RuntimeValue::Boolean(r), Comparison::Neq) => {
+                l.value != r.value
+            }
+            // Variants are equal when they are the same variant with equal payloads
+            (RuntimeValue::EnumVariant(l), RuntimeValue::EnumVariant(r), Comparison::Eq) => {
+                l.enum_id == r.enum_id && l.payload_eq(&r, location)?
+            }
+            (RuntimeValue::EnumVariant(l), RuntimeValue::EnumVariant(r), Comparison::Neq) => {
+                l.enum_id != r.enum_id || !l.payload_eq(&r, location)?
+            }
             (RuntimeValue::Null(_), RuntimeValue::Null(_), Comparison::Eq) => true,
             (RuntimeValue::Null(_), RuntimeValue::Null(_), Comparison::Neq) => false,
             (_, ref r, ref t) => {
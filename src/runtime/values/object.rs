@@ -1,5 +1,7 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+use crate::runtime::gc;
+
 use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
 
 pub type ObjectItemsType = Rc<RefCell<HashMap<String, RuntimeValue>>>;
@@ -16,8 +18,10 @@ impl Object {
     }
 
     pub fn new(items: HashMap<String, RuntimeValue>) -> Self {
+        let items = Rc::from(RefCell::from(items));
+        gc::track_object(&items);
         Object {
-            items: Rc::from(RefCell::from(items)),
+            items,
             options: RuntimeValueDetails::default(),
         }
     }
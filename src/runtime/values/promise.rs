@@ -0,0 +1,95 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use uuid::Uuid;
+
+use crate::{errors::ZephyrError, util::colors};
+
+use super::{RuntimeValue, RuntimeValueDetails, RuntimeValueUtils};
+
+#[derive(Debug, Clone)]
+pub enum PromiseState {
+    Pending,
+    Resolved(RuntimeValue),
+    Rejected(ZephyrError),
+}
+
+/// A value which will be given later, either by an async func or by a native thread
+#[derive(Debug, Clone)]
+pub struct Promise {
+    pub options: RuntimeValueDetails,
+    /// Used by threads to say which promise they are settling
+    pub id: Uuid,
+    pub state: Rc<RefCell<PromiseState>>,
+    /// Whether the promise has been awaited, rejections which never are get reported at the end
+    pub handled: Rc<Cell<bool>>,
+}
+
+impl Promise {
+    pub fn new() -> Self {
+        Promise {
+            options: RuntimeValueDetails::with_proto("promise".to_string()),
+            id: Uuid::new_v4(),
+            state: Rc::from(RefCell::from(PromiseState::Pending)),
+            handled: Rc::default(),
+        }
+    }
+
+    /// Creates an already settled promise
+    pub fn from_result(result: Result<RuntimeValue, ZephyrError>) -> Self {
+        let promise = Promise::new();
+        promise.settle(result);
+        promise
+    }
+
+    /// Gives the promise its value, this does nothing if it was already settled
+    pub fn settle(&self, result: Result<RuntimeValue, ZephyrError>) {
+        let mut state = self.state.borrow_mut();
+        if let PromiseState::Pending = *state {
+            *state = match result {
+                Ok(value) => PromiseState::Resolved(value),
+                Err(err) => PromiseState::Rejected(err),
+            };
+        }
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        matches!(*self.state.borrow(), PromiseState::Rejected(_))
+    }
+
+    pub fn is_pending(&self) -> bool {
+        matches!(*self.state.borrow(), PromiseState::Pending)
+    }
+}
+
+impl RuntimeValueUtils for Promise {
+    fn type_name(&self) -> &str {
+        "promise"
+    }
+
+    fn wrap(&self) -> RuntimeValue {
+        RuntimeValue::Promise(self.clone())
+    }
+
+    fn to_string(&self, _is_display: bool, color: bool) -> Result<String, ZephyrError> {
+        let state = match &*self.state.borrow() {
+            PromiseState::Pending => "pending".to_string(),
+            PromiseState::Resolved(value) => value.to_string(true, color, false)?,
+            PromiseState::Rejected(err) => format!("rejected: {}", err.message),
+        };
+
+        Ok(match color {
+            true => format!(
+                "{}Promise<{}{}{}>{}",
+                colors::FG_CYAN,
+                colors::COLOR_RESET,
+                state,
+                colors::FG_CYAN,
+                colors::COLOR_RESET,
+            ),
+            false => format!("Promise<{}>", state),
+        })
+    }
+}
@@ -15,7 +15,8 @@ pub struct RangeValue {
 }
 
 impl RangeValue {
-    pub fn iter_f64(&self) -> Result<Vec<f64>, ZephyrError> {
+    /// Gives (start, step, last) where last is the final value the range may give
+    pub fn bounds(&self) -> Result<(f64, f64, f64), ZephyrError> {
         let step = self
             .step
             .unwrap_or(if self.end < self.start { -1.0 } else { 1.0 });
@@ -34,8 +35,22 @@ impl RangeValue {
             });
         }
 
+        Ok((self.start, step, end))
+    }
+
+    pub fn iter_f64(&self) -> Result<Vec<f64>, ZephyrError> {
+        let (start, step, end) = self.bounds()?;
+
+        if end.is_infinite() {
+            return Err(ZephyrError {
+                message: "Cannot collect an infinite range, loop over it instead".to_string(),
+                code: ErrorCode::RangeError,
+                location: None,
+            });
+        }
+
         let values: Vec<f64> = (0..)
-            .map(|i| self.start + i as f64 * step)
+            .map(|i| start + i as f64 * step)
             .take_while(|&x| (step > 0.0 && x <= end) || (step < 0.0 && x >= end))
             .collect();
 
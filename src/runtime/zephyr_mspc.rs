@@ -1,11 +1,13 @@
 use std::sync::mpsc::Sender;
 use uuid::Uuid;
-use super::values::thread_crossing::{ThreadRuntimeValueArray};
+use super::values::thread_crossing::{ThreadRuntimeValue, ThreadRuntimeValueArray};
 
 #[derive(Debug, Clone)]
 pub struct Job {
     pub func: Uuid,
     pub args: ThreadRuntimeValueArray,
+    /// The id of the timer which posted the job, it is dropped if the timer was cleared
+    pub timer: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -13,6 +15,8 @@ pub enum MspcSendType {
     ThreadCreate,
     ThreadDestroy,
     ThreadMessage(Job),
+    /// Settles the promise with the id, Err is the error message
+    PromiseSettle(Uuid, Result<ThreadRuntimeValue, String>),
 }
 
 #[derive(Debug, Clone)]
@@ -38,4 +42,10 @@ impl MspcChannel {
             .send(MspcSendType::ThreadMessage(job))
             .unwrap_or_else(|e| panic!("Failed to send thread_message: {:?}", e))
     }
+
+    pub fn settle_promise(&mut self, id: Uuid, result: Result<ThreadRuntimeValue, String>) {
+        self.mspc
+            .send(MspcSendType::PromiseSettle(id, result))
+            .unwrap_or_else(|e| panic!("Failed to send promise_settle: {:?}", e))
+    }
 }